@@ -1,3 +1,4 @@
+#[allow(deprecated)]
 fn main() {
     multiversx_sc_meta::cli_main::<rockstake::AbiProvider>();
 }
\ No newline at end of file
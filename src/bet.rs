@@ -1,4 +1,4 @@
-use crate::types::{Bet, BetStatus, BetType, DebugBetState, DebugMatchedPart, MatchedPart};
+use crate::types::{Bet, BetPersistence, BetStatus, BetStatusFilter, BetType, DebugBetState, DebugMatchedPart, OddsInput, PlacedBet, TimeInForce, UserBetView};
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
@@ -8,10 +8,14 @@ pub trait BetModule:
     crate::events::EventsModule +
     crate::nft::NftModule +
     crate::tracker::TrackerModule +
-    crate::validation::ValidationModule 
+    crate::validation::ValidationModule +
+    crate::odds::OddsModule +
+    crate::exposure::ExposureModule +
+    crate::ledger::LedgerModule
 {
     #[payable("*")]
     #[endpoint(placeBet)]
+    #[allow_multiple_var_args]
     fn place_bet(
         &self,
         cid: ManagedBuffer,
@@ -19,6 +23,105 @@ pub trait BetModule:
         selection_id: u64,
         odds: BigUint,
         bet_type: BetType,
+        time_in_force: OptionalValue<TimeInForce>,
+        persistence: OptionalValue<BetPersistence>,
+    ) {
+        self.place_paid_bet(
+            cid,
+            market_id,
+            selection_id,
+            &odds,
+            bet_type,
+            time_in_force.into_option().unwrap_or(TimeInForce::GoodTillClose),
+            persistence.into_option().unwrap_or(BetPersistence::Lapse)
+        );
+    }
+
+    /// Same as `placeBet`, with the odds quoted as decimal, fractional, American
+    /// or implied probability and normalised onto the ladder.
+    #[payable("*")]
+    #[endpoint(placeBetInFormat)]
+    #[allow_multiple_var_args]
+    fn place_bet_in_format(
+        &self,
+        cid: ManagedBuffer,
+        market_id: u64,
+        selection_id: u64,
+        odds: OddsInput,
+        bet_type: BetType,
+        time_in_force: OptionalValue<TimeInForce>,
+        persistence: OptionalValue<BetPersistence>,
+    ) {
+        let ladder_odds = self.normalize_odds(odds, bet_type);
+        self.place_paid_bet(
+            cid,
+            market_id,
+            selection_id,
+            &BigUint::from(ladder_odds),
+            bet_type,
+            time_in_force.into_option().unwrap_or(TimeInForce::GoodTillClose),
+            persistence.into_option().unwrap_or(BetPersistence::Lapse)
+        );
+    }
+
+    /// Same as `placeBet`, paid from the caller's available balance in the
+    /// market's token instead of an attached transfer.
+    #[endpoint(placeBetFromBalance)]
+    #[allow_multiple_var_args]
+    fn place_bet_from_balance(
+        &self,
+        cid: ManagedBuffer,
+        market_id: u64,
+        selection_id: u64,
+        odds: BigUint,
+        bet_type: BetType,
+        amount: BigUint,
+        time_in_force: OptionalValue<TimeInForce>,
+        persistence: OptionalValue<BetPersistence>,
+    ) {
+        let caller = self.blockchain().get_caller();
+        self.validate_market(market_id);
+        let token_identifier = self.markets(market_id).get().token;
+        self.debit_balance(&caller, &token_identifier, &amount);
+
+        let (bet, collateral, ticket) = self.place_order(
+            &cid,
+            true,
+            &caller,
+            market_id,
+            selection_id,
+            &odds,
+            bet_type,
+            time_in_force.into_option().unwrap_or(TimeInForce::GoodTillClose),
+            persistence.into_option().unwrap_or(BetPersistence::Lapse),
+            &token_identifier,
+            0,
+            &amount
+        );
+
+        if let Some(ticket) = ticket {
+            self.send().direct_esdt(&caller, &ticket.token_identifier, ticket.token_nonce, &ticket.amount);
+        }
+        self.credit_balance(&caller, &token_identifier, &(&amount - &collateral));
+
+        self.emit_bet_placed_event(
+            &bet,
+            &token_identifier,
+            0,
+            &bet.matched_amount,
+            &bet.unmatched_amount
+        );
+    }
+
+    fn place_paid_bet(
+        &self,
+        cid: ManagedBuffer,
+        market_id: u64,
+        selection_id: u64,
+        odds: &BigUint,
+        bet_type: BetType,
+        time_in_force: TimeInForce,
+        persistence: BetPersistence,
     ) {
         let caller = self.blockchain().get_caller();
         let (token_identifier, token_nonce, total_amount) = self
@@ -26,82 +129,277 @@ pub trait BetModule:
             .egld_or_single_esdt()
             .into_tuple();
 
-        self.validate_bet_amount(&total_amount);
-        self.validate_bet_odds(&odds);
+        let (bet, collateral, ticket) = self.place_order(
+            &cid,
+            true,
+            &caller,
+            market_id,
+            selection_id,
+            odds,
+            bet_type,
+            time_in_force,
+            persistence,
+            &token_identifier,
+            token_nonce,
+            &total_amount
+        );
+
+        if let Some(ticket) = ticket {
+            self.send().direct_esdt(&caller, &ticket.token_identifier, ticket.token_nonce, &ticket.amount);
+        }
+
+        // Rotunjirea la stake și prețurile mai bune pentru Lay eliberează o parte din plată;
+        // ca la anulare, ea rămâne în balanța disponibilă a pariorului
+        self.credit_balance(&caller, &token_identifier, &(&total_amount - &collateral));
+
+        self.emit_bet_placed_event(
+            &bet,
+            &token_identifier,
+            token_nonce,
+            &bet.matched_amount,
+            &bet.unmatched_amount
+        );
+    }
+
+    /// Places several good-till-close orders paid by one EGLD or multi-ESDT transfer
+    /// in a single token. The payments must add up to the orders' amounts; tickets
+    /// are minted only when `mint_tickets` is set. What the orders don't lock is
+    /// credited to the caller's available balance.
+    #[payable("*")]
+    #[endpoint(placeBets)]
+    fn place_bets(
+        &self,
+        cid: ManagedBuffer,
+        mint_tickets: bool,
+        orders: MultiValueEncoded<MultiValue5<u64, u64, BigUint, BetType, BigUint>>,
+    ) -> ManagedVec<PlacedBet<Self::Api>> {
+        require!(!orders.is_empty(), "No bets provided");
+
+        let caller = self.blockchain().get_caller();
+        let (token_identifier, token_nonce, total_amount) = self.get_batch_payment();
+
+        let mut orders_total = BigUint::zero();
+        for order in orders.clone().into_iter() {
+            let (_, _, _, _, amount) = order.into_tuple();
+            orders_total += amount;
+        }
+        require!(orders_total == total_amount, "Payment does not match bet amounts");
+
+        let mut results = ManagedVec::new();
+        let mut tickets = ManagedVec::new();
+        let mut total_collateral = BigUint::zero();
+        for order in orders.into_iter() {
+            let (market_id, selection_id, odds, bet_type, amount) = order.into_tuple();
+            let (bet, collateral, ticket) = self.place_order(
+                &cid,
+                mint_tickets,
+                &caller,
+                market_id,
+                selection_id,
+                &odds,
+                bet_type,
+                TimeInForce::GoodTillClose,
+                BetPersistence::Lapse,
+                &token_identifier,
+                token_nonce,
+                &amount
+            );
+            total_collateral += &collateral;
+            if let Some(ticket) = ticket {
+                tickets.push(ticket);
+            }
+
+            self.emit_bet_placed_event(
+                &bet,
+                &token_identifier,
+                token_nonce,
+                &bet.matched_amount,
+                &bet.unmatched_amount
+            );
+            results.push(PlacedBet {
+                bet_id: bet.id,
+                nft_nonce: bet.nft_nonce,
+                matched_amount: bet.matched_amount,
+                unmatched_amount: bet.unmatched_amount,
+                refunded_amount: amount - collateral,
+                status: bet.status,
+            });
+        }
+
+        if !tickets.is_empty() {
+            self.send().direct_multi(&caller, &tickets);
+        }
+
+        self.credit_balance(&caller, &token_identifier, &(total_amount - total_collateral));
+
+        results
+    }
+
+    fn get_batch_payment(&self) -> (EgldOrEsdtTokenIdentifier<Self::Api>, u64, BigUint) {
+        match self.call_value().any_payment() {
+            EgldOrMultiEsdtPayment::Egld(amount) => (EgldOrEsdtTokenIdentifier::egld(), 0, amount),
+            EgldOrMultiEsdtPayment::MultiEsdt(payments) => {
+                require!(!payments.is_empty(), "No payment provided");
+                let first = payments.get(0);
+                let mut total = BigUint::zero();
+                for payment in payments.iter() {
+                    require!(
+                        payment.token_identifier == first.token_identifier
+                            && payment.token_nonce == first.token_nonce,
+                        "All payments must use the same token"
+                    );
+                    total += &payment.amount;
+                }
+                (
+                    EgldOrEsdtTokenIdentifier::esdt(first.token_identifier.clone()),
+                    first.token_nonce,
+                    total
+                )
+            }
+        }
+    }
+
+    /// Validates, matches and records one order paid with `amount`. Returns the
+    /// bet, the collateral it keeps locked and the ticket to send, if minted.
+    fn place_order(
+        &self,
+        cid: &ManagedBuffer,
+        mint_ticket: bool,
+        caller: &ManagedAddress,
+        market_id: u64,
+        selection_id: u64,
+        odds: &BigUint,
+        bet_type: BetType,
+        time_in_force: TimeInForce,
+        persistence: BetPersistence,
+        token_identifier: &EgldOrEsdtTokenIdentifier,
+        token_nonce: u64,
+        amount: &BigUint
+    ) -> (Bet<Self::Api>, BigUint, Option<EsdtTokenPayment<Self::Api>>) {
+        self.require_odds_not_migrating();
+        self.validate_bet_odds(odds);
         self.validate_market(market_id);
         self.validate_selection(market_id, selection_id);
+        self.validate_payment_token(market_id, token_identifier, token_nonce);
         
         let (final_stake, final_liability) = self.calculate_stake_and_liability(
             &bet_type,
-            &total_amount,
-            &odds
+            amount,
+            odds
         );
+        self.validate_bet_amount(&final_stake, market_id, token_identifier);
 
         let bet = self.create_bet(
             market_id,
             selection_id,
-            &caller,
+            caller,
             &final_stake,
             &final_liability,
-            &odds,
+            odds,
             bet_type,
+            persistence,
             token_identifier.clone(),
             token_nonce
         );
 
-        let (matched_amount, unmatched_amount) = self.process_bet(bet.clone());
-        let updated_bet = self.update_bet_status(bet, matched_amount.clone(), unmatched_amount.clone());
-        self.update_market_and_selection(
-            market_id,
-            selection_id,
-            &matched_amount
+        let rest_unmatched = time_in_force == TimeInForce::GoodTillClose;
+        let mut updated_bet = self.process_bet(bet, rest_unmatched);
+        require!(
+            time_in_force != TimeInForce::FillOrKill || updated_bet.matched_amount == final_stake,
+            "Bet could not be fully matched"
         );
+        if !rest_unmatched && updated_bet.unmatched_amount > BigUint::zero() {
+            // IOC: restul nematched nu intră în orderbook și se returnează imediat
+            self.release_unmatched(&mut updated_bet);
+        }
+        self.add_exposure(&updated_bet);
+        self.validate_exposure_caps(caller, market_id);
 
-        self.handle_nft_and_locked_funds(
+        let collateral = self.locked_collateral(&updated_bet);
+        let ticket = self.handle_nft_and_locked_funds(
             cid,
-            &caller,
-            &updated_bet,
-            &unmatched_amount,
-            &final_liability,
-            bet_type
+            caller,
+            &mut updated_bet,
+            &collateral,
+            mint_ticket
         );
 
-        self.emit_bet_placed_event(
-            &updated_bet,
-            &token_identifier,
-            token_nonce,
-            &matched_amount,
-            &unmatched_amount
-        );
+        (updated_bet, collateral, ticket)
     }
 
     #[endpoint(cancelBet)]
     fn cancel_bet(&self, bet_nonce: u64) {
         let caller = self.blockchain().get_caller();
+        self.cancel_unmatched(&caller, bet_nonce);
+    }
+
+    /// Cancels the caller's bets given by nonce.
+    #[endpoint(cancelBets)]
+    fn cancel_bets(&self, bet_nonces: MultiValueEncoded<u64>) {
+        let caller = self.blockchain().get_caller();
+        for bet_nonce in bet_nonces.into_iter() {
+            self.cancel_unmatched(&caller, bet_nonce);
+        }
+    }
+
+    /// Cancels every open order of the caller, optionally only on one market
+    /// or one of its selections.
+    #[endpoint(cancelAllBets)]
+    #[allow_multiple_var_args]
+    fn cancel_all_bets(
+        &self,
+        market_id: OptionalValue<u64>,
+        selection_id: OptionalValue<u64>,
+    ) -> u32 {
+        let caller = self.blockchain().get_caller();
+        let market_id = market_id.into_option();
+        let selection_id = selection_id.into_option();
+
+        // Indexul se modifică la anulare, așa că îl copiem întâi
+        let mut bet_nonces = ManagedVec::<Self::Api, u64>::new();
+        for bet_nonce in self.user_open_bets(&caller).iter() {
+            bet_nonces.push(bet_nonce);
+        }
+
+        let mut canceled = 0u32;
+        for bet_nonce in bet_nonces.iter() {
+            let bet = self.bet_by_id(bet_nonce).get();
+            if market_id.is_some_and(|id| id != bet.event)
+                || selection_id.is_some_and(|id| id != bet.selection.id)
+            {
+                continue;
+            }
+
+            self.cancel_unmatched(&caller, bet_nonce);
+            canceled += 1;
+        }
+
+        canceled
+    }
+
+    /// Pulls the unmatched part of the bet out of the book; its collateral goes
+    /// back to the caller's available balance.
+    fn cancel_unmatched(&self, caller: &ManagedAddress, bet_nonce: u64) {
+        self.require_odds_not_migrating();
         let mut bet = self.bet_by_id(bet_nonce).get();
         
-        require!(bet.bettor == caller, "Not bet owner");
+        require!(&bet.bettor == caller, "Not bet owner");
         require!(
             bet.status == BetStatus::Unmatched || bet.status == BetStatus::PartiallyMatched,
             "Bet cannot be cancelled"
         );
         
-        let refund_amount = match bet.bet_type {
-            BetType::Back => bet.unmatched_amount.clone(),
-            BetType::Lay => bet.liability.clone()
-        };
-        
         self.remove_from_orderbook(&bet);
+        self.remove_exposure(&bet);
         
-        bet.status = BetStatus::Canceled;
-        bet.unmatched_amount = BigUint::zero();
+        let refund_amount = self.release_unmatched(&mut bet);
+        self.add_exposure(&bet);
         self.bet_by_id(bet_nonce).set(&bet);
         
-        self.locked_funds(&caller).update(|val| *val -= &refund_amount);
-        
-        self.send().direct(&caller, &bet.payment_token,0, &refund_amount);
+        self.release_to_balance(caller, &bet.payment_token, &refund_amount);
     }
 
+    #[payable("*")]
     #[endpoint(updateBet)]
 #[allow_multiple_var_args]
 fn update_bet(
@@ -111,6 +409,7 @@ fn update_bet(
     new_amount: OptionalValue<BigUint>,
 ) {
     let caller = self.blockchain().get_caller();
+    self.require_odds_not_migrating();
     let mut bet = self.bet_by_id(bet_nonce).get();
     
     require!(bet.bettor == caller, "Not bet owner");
@@ -123,14 +422,15 @@ fn update_bet(
         "Must provide new odds or amount"
     );
 
-    // Păstrăm matched_parts neschimbate
-    let matched_parts = bet.matched_parts.clone();
+    // Un Lay poate adăuga liability când mută cota în sus
+    let (payment_token, payment_nonce, top_up) = self.call_value().egld_or_single_esdt().into_tuple();
+    if top_up > BigUint::zero() {
+        self.validate_payment_token(bet.event, &payment_token, payment_nonce);
+    }
+
+    let old_collateral = self.locked_collateral(&bet);
+    let available_collateral = &old_collateral + &top_up;
     let old_unmatched = bet.unmatched_amount.clone();
-    
-    let old_liability = match bet.bet_type {
-        BetType::Back => BigUint::zero(),
-        BetType::Lay => bet.liability.clone()
-    };
 
     let update_odds = match &new_odds {
         OptionalValue::Some(odds) => {
@@ -146,74 +446,67 @@ fn update_bet(
                 amount <= &old_unmatched,
                 "New amount cannot exceed unmatched amount"
             );
+            require!(amount > &BigUint::zero(), "New amount must be greater than zero");
             amount.clone()
         },
         OptionalValue::None => old_unmatched.clone()
     };
 
-    let refund_amount = if new_amount.is_some() {
-        old_unmatched.clone() - &new_unmatched
-    } else {
-        BigUint::zero()
-    };
-
-    if new_amount.is_some() || new_odds.is_some() {
+    // Doar schimbarea cotei mută pariul la coada nivelului; reducerea sumei își păstrează locul
+    let reduction = &old_unmatched - &new_unmatched;
+    let odds_changed = update_odds != bet.odd;
+    self.remove_exposure(&bet);
+    if odds_changed {
         self.remove_from_orderbook(&bet);
+    } else if reduction > BigUint::zero() {
+        self.reduce_in_orderbook(&bet, &reduction);
     }
 
-    bet.odd = update_odds.clone();
-    bet.unmatched_amount = new_unmatched.clone();
-    
-    // Calculăm stake_amount ca suma tuturor părților matched plus unmatched
-    bet.stake_amount = BigUint::zero();
-    for part in bet.matched_parts.iter() {
-        bet.stake_amount += &part.amount;
-    }
-    bet.stake_amount += &new_unmatched;
-
-    // Recalculăm profitul potențial bazat pe matched_parts
+    bet.odd = update_odds;
+    bet.unmatched_amount = new_unmatched;
+    bet.stake_amount = &bet.matched_amount + &bet.unmatched_amount;
+    self.validate_bet_amount(&bet.stake_amount, bet.event, &bet.payment_token);
     bet.potential_profit = self.calculate_total_potential_profit(&bet);
-
-    // Calculăm liability total
-    let mut total_liability = BigUint::zero();
-    
-    // Pentru părțile matched
-    for part in bet.matched_parts.iter() {
-        let (_, matched_liability) = self.calculate_stake_and_liability(
-            &bet.bet_type,
-            &part.amount,
-            &part.odds
-        );
-        total_liability += matched_liability;
-    }
-
-    // Pentru partea unmatched
-    if new_unmatched > BigUint::zero() {
-        let (_, unmatched_liability) = self.calculate_stake_and_liability(
-            &bet.bet_type,
-            &new_unmatched,
-            &update_odds
-        );
-        total_liability += unmatched_liability;
+    if bet.bet_type == BetType::Lay {
+        bet.liability = self.calculate_required_collateral(&bet);
     }
+    require!(
+        self.locked_collateral(&bet) <= available_collateral,
+        "Insufficient liability for new odds"
+    );
 
-    bet.liability = total_liability.clone();
+    let updated_bet = if odds_changed {
+        self.process_bet(bet, true)
+    } else {
+        self.bet_by_id(bet_nonce).set(&bet);
+        bet
+    };
+    self.add_exposure(&updated_bet);
+    self.validate_exposure_caps(&caller, updated_bet.event);
 
-    let (matched_amount, unmatched_amount) = self.process_bet(bet.clone());
+    let new_collateral = self.locked_collateral(&updated_bet);
+    self.unlock_funds(&caller, &updated_bet.payment_token, &old_collateral);
+    self.lock_funds(&caller, &updated_bet.payment_token, &new_collateral);
 
-    self.locked_funds(&caller).update(|val| {
-        *val -= &old_liability;
-        *val += &total_liability;
-    });
+    // Colateralul eliberat rămâne în soldul userului
+    let refund_amount = available_collateral - &new_collateral;
+    self.credit_balance(&caller, &updated_bet.payment_token, &refund_amount);
+}
 
-    let updated_bet = self.update_bet_status(bet, matched_amount, unmatched_amount);
-    self.bet_by_id(bet_nonce).set(&updated_bet);
 
-    if refund_amount > BigUint::zero() {
-        self.send().direct(&caller, &updated_bet.payment_token, 0, &refund_amount);
+/// Contracts deployed before `last_bet_id` existed keyed every bet by its
+/// ticket nonce, so new ids continue after the last ticket minted.
+fn upgrade_bet_ids(&self) {
+    if !self.last_bet_id().is_empty() || self.bet_nft_token().is_empty() {
+        return;
     }
-}
 
+    let last_ticket_nonce = self.blockchain().get_current_esdt_nft_nonce(
+        &self.blockchain().get_sc_address(),
+        self.bet_nft_token().get_token_id_ref(),
+    );
+    self.last_bet_id().set(last_ticket_nonce);
+}
 
 fn create_bet(
     &self,
@@ -224,6 +517,7 @@ fn create_bet(
     liability: &BigUint,
     odds: &BigUint,
     bet_type: BetType,
+    persistence: BetPersistence,
     token_identifier: EgldOrEsdtTokenIdentifier<Self::Api>,
     token_nonce: u64
 ) -> Bet<Self::Api> {
@@ -233,9 +527,13 @@ fn create_bet(
         .find(|s| s.id == selection_id)
         .unwrap_or_else(|| sc_panic!("Invalid selection"))
         .clone();
-    let bet_id = self.get_last_bet_id() + 1;
+    let bet_id = self.last_bet_id().update(|id| {
+        *id += 1;
+        *id
+    });
     
     Bet {
+        id: bet_id,
         bettor: caller.clone(),
         event: market_id,
         selection,
@@ -248,80 +546,40 @@ fn create_bet(
         odd: odds.clone(),
         bet_type,
         status: BetStatus::Unmatched,
+        persistence,
         payment_token: token_identifier,
         payment_nonce: token_nonce,
-        nft_nonce: bet_id,
+        nft_nonce: 0,
         created_at: self.blockchain().get_block_timestamp()
     }
 }
 
-fn update_bet_status(
-    &self,
-    mut bet: Bet<Self::Api>,
-    matched_amount: BigUint,
-    unmatched_amount: BigUint
-) -> Bet<Self::Api> {
-    bet.matched_amount = matched_amount.clone();
-    bet.unmatched_amount = unmatched_amount.clone();
-    bet.status = if matched_amount > BigUint::zero() {
-        if unmatched_amount > BigUint::zero() {
-            BetStatus::PartiallyMatched
-        } else {
-            BetStatus::Matched
-        }
-    } else {
-        BetStatus::Unmatched
-    };
-    bet
-}
-
-
-    fn update_market_and_selection(
-        &self,
-        market_id: u64,
-        selection_id: u64,
-        matched_amount: &BigUint,
-    ) {
-        let mut market = self.markets(market_id).get();
-        let selection_index = market
-            .selections
-            .iter()
-            .position(|s| s.id == selection_id)
-            .unwrap_or_else(|| sc_panic!("Invalid selection"));
-        
-        let mut selection = market.selections.get(selection_index);
-        selection.priority_queue = self.selection_tracker(market_id, selection_id).get();
-        
-        let _ = market.selections.set(selection_index, selection);
-        market.total_matched_amount += matched_amount;
-        self.markets(market_id).set(&market);
-    }
-
     fn handle_nft_and_locked_funds(
         &self,
-        cid: ManagedBuffer,
+        cid: &ManagedBuffer,
         caller: &ManagedAddress<Self::Api>,
-        bet: &Bet<Self::Api>,
-        unmatched_amount: &BigUint,
-        liability: &BigUint,
-        bet_type: BetType
-    ) {
-        let bet_nft_nonce = self.mint_bet_nft(cid, bet);
-        self.bet_by_id(bet.nft_nonce).set(bet);
-
-        self.market_bet_ids(bet.event).insert(bet.nft_nonce);
-        let total_locked = match bet_type {
-            BetType::Back => unmatched_amount.clone(),
-            BetType::Lay => liability.clone(),
+        bet: &mut Bet<Self::Api>,
+        collateral: &BigUint,
+        mint_ticket: bool
+    ) -> Option<EsdtTokenPayment<Self::Api>> {
+        let ticket = if mint_ticket {
+            let bet_nft_nonce = self.mint_bet_nft(cid.clone(), bet);
+            bet.nft_nonce = bet_nft_nonce;
+            Some(EsdtTokenPayment::new(
+                self.bet_nft_token().get_token_id(),
+                bet_nft_nonce,
+                BigUint::from(1u64)
+            ))
+        } else {
+            None
         };
-        self.locked_funds(caller).update(|current_locked| *current_locked += &total_locked);
+        self.bet_by_id(bet.id).set(&*bet);
 
-        self.send().direct_esdt(
-            caller,
-            self.bet_nft_token().get_token_id_ref(),
-            bet_nft_nonce,
-            &BigUint::from(1u64)
-        );
+        self.market_bet_ids(bet.event).insert(bet.id);
+        self.user_bets(caller).push(&bet.id);
+        self.lock_funds(caller, &bet.payment_token, collateral);
+
+        ticket
     }
 
     fn emit_bet_placed_event(
@@ -367,18 +625,69 @@ fn update_bet_status(
         odds: &BigUint
     ) -> BigUint {
         match bet_type {
-            BetType::Back => {
-                (odds - &BigUint::from(100u32)) * stake / &BigUint::from(100u32)
-            },
+            BetType::Back => self.calculate_liability(stake, odds),
             BetType::Lay => stake.clone()
         }
     }
 
-    fn get_last_bet_id(&self) -> u64 {
-        self.blockchain().get_current_esdt_nft_nonce(
-            &self.blockchain().get_sc_address(),
-            self.bet_nft_token().get_token_id_ref(),
-        )
+    /// Pages through the bets of `bettor` kept under `status_filter`. `All` and
+    /// `Settled` follow placement and settlement order; `Open` and `Matched`
+    /// are unordered sets.
+    #[view(getUserBets)]
+    fn get_user_bets(
+        &self,
+        bettor: ManagedAddress,
+        status_filter: BetStatusFilter,
+        from: u32,
+        count: u32
+    ) -> ManagedVec<Self::Api, UserBetView<Self::Api>> {
+        let len = match status_filter {
+            BetStatusFilter::All => self.user_bets(&bettor).len(),
+            BetStatusFilter::Open => self.user_open_bets(&bettor).len(),
+            BetStatusFilter::Matched => self.user_matched_bets(&bettor).len(),
+            BetStatusFilter::Settled => self.user_settled_bets(&bettor).len(),
+        };
+        let first = from as usize + 1;
+        let last = len.min(from as usize + count as usize);
+
+        // Indexurile pornesc de la 1
+        let mut views = ManagedVec::new();
+        for index in first..=last {
+            let bet_id = match status_filter {
+                BetStatusFilter::All => self.user_bets(&bettor).get(index),
+                BetStatusFilter::Open => self.user_open_bets(&bettor).get_by_index(index),
+                BetStatusFilter::Matched => self.user_matched_bets(&bettor).get_by_index(index),
+                BetStatusFilter::Settled => self.user_settled_bets(&bettor).get(index),
+            };
+
+            let bet = self.bet_by_id(bet_id).get();
+            views.push(UserBetView {
+                nonce: bet.id,
+                market_id: bet.event,
+                selection_id: bet.selection.id,
+                bet_type: bet.bet_type,
+                odds: bet.odd,
+                stake: bet.stake_amount,
+                matched: bet.matched_amount,
+                unmatched: bet.unmatched_amount,
+                liability: bet.liability,
+                potential_profit: bet.potential_profit,
+                status: bet.status,
+                nft_nonce: bet.nft_nonce,
+                created_at: bet.created_at
+            });
+        }
+        views
+    }
+
+    #[view(getUserBetCount)]
+    fn get_user_bet_count(&self, bettor: ManagedAddress) -> usize {
+        self.user_bets(&bettor).len()
+    }
+
+    #[view(getUserOpenBetIds)]
+    fn get_user_open_bet_ids(&self, bettor: ManagedAddress) -> MultiValueEncoded<u64> {
+        self.user_open_bets(&bettor).iter().collect()
     }
 
 //     #[view(getDebugBetState)]
@@ -0,0 +1,210 @@
+use crate::constants::constants::{BASIS_POINTS, GOAL_LINE_SCALE, MAX_SELECTIONS, QUARTER_LINE};
+use crate::types::{MarketKind, MarketType, WinningSelection};
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+// Ordinea selecțiilor pentru rezultatul final: 1 gazde, 2 oaspeți, 3 egal
+const HOME: u64 = 1;
+const AWAY: u64 = 2;
+const DRAW: u64 = 3;
+
+#[multiversx_sc::module]
+pub trait CatalogueModule:
+    crate::storage::StorageModule
+    + crate::events::EventsModule
+{
+    /// Adds a market type to the catalogue. Markets created from it get their
+    /// selections from the type, and are settled from the reported score.
+    #[only_owner]
+    #[endpoint(registerMarketType)]
+    fn register_market_type(&self, kind: MarketKind, line: i64, description: ManagedBuffer) -> u64 {
+        self.validate_market_line(kind, line);
+
+        let market_type_id = self.market_type_counter().get() + 1;
+        self.market_type_counter().set(market_type_id);
+        self.market_types(market_type_id).set(MarketType { kind, line, description });
+
+        self.market_type_registered_event(market_type_id, kind, line);
+        market_type_id
+    }
+
+    /// Tipurile 1-3 sunt cele fixe de dinainte de catalog.
+    fn init_market_types(&self) {
+        if self.market_type_counter().get() > 0 {
+            return;
+        }
+        self.register_market_type(MarketKind::FullTimeResult, 0, ManagedBuffer::from(b"FullTime Result"));
+        self.register_market_type(MarketKind::OverUnder, 250, ManagedBuffer::from(b"Total Goals O/U 2.5"));
+        self.register_market_type(MarketKind::BothTeamsToScore, 0, ManagedBuffer::from(b"Both Teams To Score"));
+    }
+
+    fn validate_market_line(&self, kind: MarketKind, line: i64) {
+        let valid = match kind {
+            MarketKind::OverUnder => line > 0 && line % QUARTER_LINE == 0,
+            MarketKind::AsianHandicap => line % QUARTER_LINE == 0,
+            MarketKind::EuropeanHandicap => line % GOAL_LINE_SCALE == 0,
+            MarketKind::CorrectScore | MarketKind::WinningMargin => line > 0 && line < MAX_SELECTIONS as i64,
+            _ => line == 0,
+        };
+        require!(valid, "Invalid market line");
+        require!(
+            self.selection_count(kind, line) as usize <= MAX_SELECTIONS,
+            "Too many selections"
+        );
+    }
+
+    #[view(getMarketType)]
+    fn get_market_type(&self, market_type_id: u64) -> MarketType<Self::Api> {
+        require!(!self.market_types(market_type_id).is_empty(), "Invalid market type");
+        self.market_types(market_type_id).get()
+    }
+
+    /// Correct Score: `h * (line + 1) + a + 1` for each score up to `line`, then
+    /// "any other". Half-time/Full-time: `3 * ht + ft + 1` with 0 home, 1 away,
+    /// 2 draw. Winning Margin: home by 1..=line, away by 1..=line, then draw; the
+    /// last margin of each side also covers larger wins.
+    fn selection_count(&self, kind: MarketKind, line: i64) -> u64 {
+        match kind {
+            MarketKind::FullTimeResult
+            | MarketKind::EuropeanHandicap
+            | MarketKind::DoubleChance => 3,
+            MarketKind::OverUnder
+            | MarketKind::AsianHandicap
+            | MarketKind::DrawNoBet
+            | MarketKind::BothTeamsToScore => 2,
+            MarketKind::CorrectScore => {
+                let side = line as u64 + 1;
+                side * side + 1
+            },
+            MarketKind::HalfTimeFullTime => 9,
+            MarketKind::WinningMargin => 2 * line as u64 + 1,
+        }
+    }
+
+    /// Winning selections of a market of the given type for the reported score.
+    fn resolve_market_type(
+        &self,
+        market_type: &MarketType<Self::Api>,
+        score_home: u32,
+        score_away: u32,
+        half_time: Option<(u32, u32)>
+    ) -> ManagedVec<WinningSelection> {
+        let home = score_home as i64;
+        let away = score_away as i64;
+        let line = market_type.line;
+        let mut winners = ManagedVec::new();
+
+        match market_type.kind {
+            MarketKind::FullTimeResult => {
+                winners.push(self.full_win(self.match_result(home - away)));
+            },
+            MarketKind::OverUnder => {
+                // Over e selecția 1, Under selecția 2
+                winners = self.resolve_goal_line((home + away) * GOAL_LINE_SCALE - line);
+            },
+            MarketKind::AsianHandicap => {
+                winners = self.resolve_goal_line((home - away) * GOAL_LINE_SCALE + line);
+            },
+            MarketKind::EuropeanHandicap => {
+                winners.push(self.full_win(self.match_result(home - away + line / GOAL_LINE_SCALE)));
+            },
+            MarketKind::DoubleChance => {
+                // 1 gazde sau egal, 2 oaspeți sau egal, 3 gazde sau oaspeți
+                let (first, second) = match self.match_result(home - away) {
+                    HOME => (1, 3),
+                    AWAY => (2, 3),
+                    _ => (1, 2),
+                };
+                winners.push(self.full_win(first));
+                winners.push(self.full_win(second));
+            },
+            MarketKind::DrawNoBet => {
+                match self.match_result(home - away) {
+                    DRAW => {
+                        winners.push(self.push_outcome(HOME));
+                        winners.push(self.push_outcome(AWAY));
+                    },
+                    result => winners.push(self.full_win(result)),
+                }
+            },
+            MarketKind::BothTeamsToScore => {
+                winners.push(self.full_win(if home > 0 && away > 0 { 1 } else { 2 }));
+            },
+            MarketKind::CorrectScore => {
+                let selection_id = if home <= line && away <= line {
+                    (home * (line + 1) + away + 1) as u64
+                } else {
+                    self.selection_count(MarketKind::CorrectScore, line)
+                };
+                winners.push(self.full_win(selection_id));
+            },
+            MarketKind::HalfTimeFullTime => {
+                let (half_time_home, half_time_away) =
+                    half_time.unwrap_or_else(|| sc_panic!("Half-time score required"));
+                require!(
+                    half_time_home <= score_home && half_time_away <= score_away,
+                    "Invalid half-time score"
+                );
+                let half_time_result = self.match_result(half_time_home as i64 - half_time_away as i64);
+                let full_time_result = self.match_result(home - away);
+                winners.push(self.full_win(3 * (half_time_result - 1) + full_time_result));
+            },
+            MarketKind::WinningMargin => {
+                let margin = (home - away).abs().min(line) as u64;
+                let selection_id = match self.match_result(home - away) {
+                    HOME => margin,
+                    AWAY => line as u64 + margin,
+                    _ => 2 * line as u64 + 1,
+                };
+                winners.push(self.full_win(selection_id));
+            },
+        }
+        winners
+    }
+
+    fn match_result(&self, goal_difference: i64) -> u64 {
+        if goal_difference > 0 { HOME }
+        else if goal_difference < 0 { AWAY }
+        else { DRAW }
+    }
+
+    /// Selecția 1 câștigă când `margin` e pozitiv, selecția 2 când e negativ, iar
+    /// la zero mizele se returnează. O linie sfert e jumătate pe fiecare linie vecină.
+    fn resolve_goal_line(&self, margin: i64) -> ManagedVec<WinningSelection> {
+        let parts = if margin % (2 * QUARTER_LINE) != 0 {
+            [margin - QUARTER_LINE, margin + QUARTER_LINE]
+        } else {
+            [margin, margin]
+        };
+
+        let half = BASIS_POINTS / 2;
+        let (mut first_win, mut second_win, mut void) = (0u64, 0u64, 0u64);
+        for part in parts {
+            if part > 0 { first_win += half; }
+            else if part < 0 { second_win += half; }
+            else { void += half; }
+        }
+
+        let mut winners = ManagedVec::new();
+        if first_win + void > 0 {
+            winners.push(WinningSelection { selection_id: 1, win_fraction_bps: first_win, void_fraction_bps: void });
+        }
+        if second_win + void > 0 {
+            winners.push(WinningSelection { selection_id: 2, win_fraction_bps: second_win, void_fraction_bps: void });
+        }
+        winners
+    }
+
+    fn full_win(&self, selection_id: u64) -> WinningSelection {
+        WinningSelection { selection_id, win_fraction_bps: BASIS_POINTS, void_fraction_bps: 0 }
+    }
+
+    fn push_outcome(&self, selection_id: u64) -> WinningSelection {
+        WinningSelection { selection_id, win_fraction_bps: 0, void_fraction_bps: BASIS_POINTS }
+    }
+
+    #[view(getMarketTypeOf)]
+    fn get_market_type_of(&self, market_id: u64) -> u64 {
+        self.market_type_of(market_id).get()
+    }
+}
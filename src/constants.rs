@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod constants {
 
     pub const IPFS_GATEWAY: &[u8] = "https://ipfs.io/ipfs/".as_bytes();
@@ -8,13 +9,39 @@ pub mod constants {
     pub const TOKEN_TICKER: &[u8] = b"BET";
     
     //Bet
-    pub const MIN_ODDS: u32 = 101;      // 1.01
-    pub const MAX_ODDS: u32 = 100000;   // 1000.00
+    // Cotele sunt în virgulă fixă: odds_scale înseamnă cota 1.00
+    pub const DEFAULT_ODDS_SCALE: u64 = 10_000;
+    // Scara folosită înainte ca ea să fie configurabilă (sutimi)
+    pub const LEGACY_ODDS_SCALE: u64 = 100;
+
+    // Probabilitățile implicite sunt în puncte de bază
+    pub const BASIS_POINTS: u64 = 10_000;
+
+    // Scara implicită de cote, în stil Betfair, în sutimi: (până la cota, pasul)
+    pub const DEFAULT_MIN_ODDS: u64 = 101; // 1.01
+    pub const DEFAULT_ODDS_LADDER: [(u64, u64); 10] = [
+        (200, 1),       // 1.01 - 2.00 din 0.01
+        (300, 2),       // 2.00 - 3.00 din 0.02
+        (400, 5),       // 3.00 - 4.00 din 0.05
+        (600, 10),      // 4.00 - 6.00 din 0.10
+        (1000, 20),     // 6.00 - 10.00 din 0.20
+        (2000, 50),     // 10.00 - 20.00 din 0.50
+        (3000, 100),    // 20.00 - 30.00 din 1.00
+        (5000, 200),    // 30.00 - 50.00 din 2.00
+        (10000, 500),   // 50.00 - 100.00 din 5.00
+        (100000, 1000), // 100.00 - 1000.00 din 10.00
+    ];
 
     // Market constants
     pub const MAX_SELECTIONS: usize = 100;
-    
-    // User constants
-    pub const MAX_USER_EXPOSURE: u64 = 10_000_000_000_000_000_000; // 10 EGLD
+    // Câte niveluri citim cel mult ca să găsim locul unui preț nou în orderbook
+    pub const MAX_LEVEL_DEPTH: usize = 50;
+    // Prima versiune avea trei tipuri, găsite după poziția pieței în eveniment
+    pub const LEGACY_MARKET_TYPES: u64 = 3;
+
+    // Liniile de handicap și over/under sunt în sutimi de gol
+    pub const GOAL_LINE_SCALE: i64 = 100;
+    // Liniile asiatice sfert (2.25, -0.75) împart miza pe două linii vecine
+    pub const QUARTER_LINE: i64 = 25;
 }
 
@@ -1,4 +1,4 @@
-use crate::types::{BetType};
+use crate::types::{BetType, EventStatus, MarketKind, SelfTradePrevention, WinningSelection};
 
 multiversx_sc::imports!();
 
@@ -21,9 +21,53 @@ pub trait EventsModule {
         #[indexed] collateral: &BigUint
     );
 
+    #[event("deposit")]
+    fn deposit_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] token_identifier: &EgldOrEsdtTokenIdentifier,
+        amount: &BigUint,
+    );
+
+    #[event("withdraw")]
+    fn withdraw_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] token_identifier: &EgldOrEsdtTokenIdentifier,
+        amount: &BigUint,
+    );
+
+    #[event("fee_charged")]
+    fn fee_charged_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] market_id: u64,
+        #[indexed] token_identifier: &EgldOrEsdtTokenIdentifier,
+        amount: &BigUint,
+    );
+
+    #[event("market_voided")]
+    fn market_voided_event(
+        &self,
+        #[indexed] market_id: u64,
+        #[indexed] timestamp: u64,
+        reason: &ManagedBuffer,
+    );
+
+    #[event("market_type_registered")]
+    fn market_type_registered_event(
+        &self,
+        #[indexed] market_type_id: u64,
+        #[indexed] kind: MarketKind,
+        #[indexed] line: i64,
+    );
+
     #[event("market_closed")]
     fn market_closed_event(&self, #[indexed] market_id: u64, #[indexed] timestamp: u64);
 
+    #[event("market_in_play")]
+    fn market_in_play_event(&self, #[indexed] market_id: u64, #[indexed] timestamp: u64);
+
     #[event("reward_distributed")]
     fn reward_distributed_event(
         &self,
@@ -61,6 +105,17 @@ pub trait EventsModule {
         #[indexed] odds: &BigUint,
     );
 
+    #[event("eventCreated")]
+    fn event_created_event(
+        &self,
+        #[indexed] event_id: u64,
+        #[indexed] competition: &ManagedBuffer,
+        #[indexed] start_timestamp: u64,
+    );
+
+    #[event("event_status_changed")]
+    fn event_status_changed_event(&self, #[indexed] event_id: u64, #[indexed] status: EventStatus);
+
     #[event("marketCreated")]
     fn market_created_event(
         &self,
@@ -77,6 +132,25 @@ pub trait EventsModule {
         #[indexed] current_counter: u64,
     );
 
+    /// Every winning selection of the market, with its dead-heat fractions.
+    #[event("marketWinners")]
+    fn market_winners_event(
+        &self,
+        #[indexed] market_id: u64,
+        winners: &ManagedVec<WinningSelection>,
+    );
+
+    #[event("self_trade_prevented")]
+    fn self_trade_prevented_event(
+        &self,
+        #[indexed] bettor: &ManagedAddress,
+        #[indexed] market_id: u64,
+        #[indexed] selection_id: u64,
+        #[indexed] incoming_bet_id: u64,
+        #[indexed] resting_bet_id: u64,
+        #[indexed] mode: SelfTradePrevention,
+    );
+
     #[event("bet_refunded")]
     fn bet_refunded_event(
         &self,
@@ -0,0 +1,207 @@
+use crate::types::{Bet, BetType, ExposurePosition, MarketKind};
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+#[multiversx_sc::module]
+pub trait ExposureModule:
+    crate::storage::StorageModule
+    + crate::events::EventsModule
+    + crate::odds::OddsModule
+    + crate::validation::ValidationModule
+{
+    fn add_exposure(&self, bet: &Bet<Self::Api>) {
+        self.apply_exposure(bet, true);
+    }
+
+    fn remove_exposure(&self, bet: &Bet<Self::Api>) {
+        self.apply_exposure(bet, false);
+    }
+
+    /// Adds or takes the bet out of the bettor's position on its selection and
+    /// refreshes the bettor's worst-case loss on the market and overall.
+    fn apply_exposure(&self, bet: &Bet<Self::Api>, add: bool) {
+        let stake = bet.stake_amount.clone();
+        // Scoatem exact ce am adăugat, altfel rotunjirile pe părți pot diferi
+        let at_risk = if add {
+            let mut at_risk = self.calculate_liability(&bet.unmatched_amount, &bet.odd);
+            for part in bet.matched_parts.iter() {
+                at_risk += self.calculate_liability(&part.amount, &part.odds);
+            }
+            self.bet_at_risk(bet.id).set(&at_risk);
+            at_risk
+        } else {
+            self.bet_at_risk(bet.id).take()
+        };
+
+        let mut position = self.get_user_position(&bet.bettor, bet.event, bet.selection.id);
+        let (side_stake, side_risk) = match bet.bet_type {
+            BetType::Back => (&mut position.back_stake, &mut position.back_profit),
+            BetType::Lay => (&mut position.lay_stake, &mut position.lay_liability),
+        };
+        if add {
+            *side_stake += &stake;
+            *side_risk += &at_risk;
+        } else {
+            *side_stake -= &stake;
+            *side_risk -= &at_risk;
+        }
+        self.user_position(&bet.bettor, bet.event, bet.selection.id).set(position);
+
+        self.refresh_market_exposure(&bet.bettor, bet.event);
+    }
+
+    fn refresh_market_exposure(&self, user: &ManagedAddress, market_id: u64) {
+        let token = self.markets(market_id).get().token;
+        let new_exposure = self.calculate_market_exposure(user, market_id);
+        let exposure_mapper = self.user_market_exposure(user, market_id);
+        let old_exposure = exposure_mapper.get();
+
+        self.user_total_exposure(user, &token).update(|total| {
+            *total += &new_exposure;
+            *total -= &old_exposure;
+        });
+        self.total_exposure(&token).update(|total| {
+            *total += &new_exposure;
+            *total -= &old_exposure;
+        });
+        exposure_mapper.set(new_exposure);
+    }
+
+    /// Worst-case loss over the outcomes of the market, netting backs and lays on
+    /// every selection: in an outcome the winning selections pay backs their
+    /// profit and lays their liability, the others lose backs and keep lays'
+    /// stakes. One selection wins at a time, except in a Double Chance market,
+    /// where each match result makes two of them win. Dead heats, quarter lines
+    /// and pushes are averages of these outcomes and a void, so never worse.
+    fn calculate_market_exposure(&self, user: &ManagedAddress, market_id: u64) -> BigUint {
+        let market = self.markets(market_id).get();
+
+        // Rezultatul când toate selecțiile pierd, plus ce schimbă câștigul fiecăreia
+        let mut all_lost = BigInt::zero();
+        let mut win_changes = ManagedVec::<Self::Api, BigInt>::new();
+        for selection in market.selections.iter() {
+            let position = self.get_user_position(user, market_id, selection.id);
+            let if_won = BigInt::from(position.back_profit) - BigInt::from(position.lay_liability);
+            let if_lost = BigInt::from(position.lay_stake) - BigInt::from(position.back_stake);
+            all_lost += &if_lost;
+            win_changes.push(if_won - if_lost);
+        }
+
+        let market_type_id = self.market_type_of(market_id).get();
+        let double_chance = !self.market_types(market_type_id).is_empty()
+            && self.market_types(market_type_id).get().kind == MarketKind::DoubleChance;
+
+        let mut worst_result = BigInt::zero();
+        if double_chance {
+            // Gazde: 1 și 3, oaspeți: 2 și 3, egal: 1 și 2
+            for (first, second) in [(0, 2), (1, 2), (0, 1)] {
+                let mut result = all_lost.clone();
+                result += &*win_changes.get(first);
+                result += &*win_changes.get(second);
+                if result < worst_result {
+                    worst_result = result;
+                }
+            }
+        } else {
+            for win_change in win_changes.iter() {
+                let result = &all_lost + &*win_change;
+                if result < worst_result {
+                    worst_result = result;
+                }
+            }
+        }
+
+        (-worst_result).into_big_uint().unwrap_or_sc_panic("Invalid exposure")
+    }
+
+    fn get_user_position(
+        &self,
+        user: &ManagedAddress,
+        market_id: u64,
+        selection_id: u64
+    ) -> ExposurePosition<Self::Api> {
+        let mapper = self.user_position(user, market_id, selection_id);
+        if mapper.is_empty() {
+            ExposurePosition {
+                back_stake: BigUint::zero(),
+                back_profit: BigUint::zero(),
+                lay_stake: BigUint::zero(),
+                lay_liability: BigUint::zero(),
+            }
+        } else {
+            mapper.get()
+        }
+    }
+
+    fn validate_exposure_caps(&self, user: &ManagedAddress, market_id: u64) {
+        let token = self.markets(market_id).get().token;
+        let market_cap = self.market_exposure_cap(market_id);
+        if !market_cap.is_empty() {
+            require!(
+                self.user_market_exposure(user, market_id).get() <= market_cap.get(),
+                "Market exposure limit exceeded"
+            );
+        }
+
+        let user_cap = if self.user_exposure_cap(user, &token).is_empty() {
+            self.max_user_exposure(&token)
+        } else {
+            self.user_exposure_cap(user, &token)
+        };
+        if !user_cap.is_empty() {
+            require!(
+                self.user_total_exposure(user, &token).get() <= user_cap.get(),
+                "Maximum exposure exceeded"
+            );
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setMaxUserExposure)]
+    fn set_max_user_exposure(&self, token: EgldOrEsdtTokenIdentifier, cap: OptionalValue<BigUint>) {
+        match cap {
+            OptionalValue::Some(cap) => self.max_user_exposure(&token).set(cap),
+            OptionalValue::None => self.max_user_exposure(&token).clear(),
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setUserExposureCap)]
+    fn set_user_exposure_cap(
+        &self,
+        user: ManagedAddress,
+        token: EgldOrEsdtTokenIdentifier,
+        cap: OptionalValue<BigUint>
+    ) {
+        match cap {
+            OptionalValue::Some(cap) => self.user_exposure_cap(&user, &token).set(cap),
+            OptionalValue::None => self.user_exposure_cap(&user, &token).clear(),
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setMarketExposureCap)]
+    fn set_market_exposure_cap(&self, market_id: u64, cap: OptionalValue<BigUint>) {
+        require!(!self.markets(market_id).is_empty(), "Invalid market");
+        match cap {
+            OptionalValue::Some(cap) => self.market_exposure_cap(market_id).set(cap),
+            OptionalValue::None => self.market_exposure_cap(market_id).clear(),
+        }
+    }
+
+    /// The user's worst-case loss on the market and across all open markets in
+    /// the market's token.
+    #[view(getUserExposure)]
+    fn get_user_exposure(&self, user: ManagedAddress, market_id: u64) -> MultiValue2<BigUint, BigUint> {
+        let token = self.markets(market_id).get().token;
+        (
+            self.user_market_exposure(&user, market_id).get(),
+            self.user_total_exposure(&user, &token).get()
+        ).into()
+    }
+
+    #[view(getTotalExposure)]
+    fn get_total_exposure(&self, token: EgldOrEsdtTokenIdentifier) -> BigUint {
+        self.total_exposure(&token).get()
+    }
+}
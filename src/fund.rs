@@ -1,4 +1,5 @@
-use crate::{errors::{ERR_INVALID_MARKET, ERR_MARKET_NOT_CLOSED, ERR_MARKET_NOT_SETTLED}, types::{Bet, BetStatus, BetType, MarketStatus, MarketType, ProcessingProgress, ProcessingStatus}};
+use crate::constants::constants::BASIS_POINTS;
+use crate::{types::{Bet, BetPersistence, BetStatus, BetType, Market, MarketStatus, ProcessingProgress, ProcessingStatus, WinningSelection}};
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
@@ -7,72 +8,64 @@ pub trait FundModule:
     crate::storage::StorageModule
     + crate::events::EventsModule
     + crate::nft::NftModule
+    + crate::tracker::TrackerModule
+    + crate::validation::ValidationModule
+    + crate::odds::OddsModule
+    + crate::exposure::ExposureModule
+    + crate::ledger::LedgerModule
+    + crate::catalogue::CatalogueModule
 {
     fn handle_expired_market(&self, market_id: u64) {
+        self.require_odds_not_migrating();
         let mut market = self.markets(market_id).get();
-        market.market_status = MarketStatus::Closed;
+        // O piață cu in-play activ trece în InPlay la start, nu se închide
+        let going_in_play = market.market_status == MarketStatus::Open && market.in_play;
+        market.market_status = if going_in_play {
+            MarketStatus::InPlay
+        } else {
+            MarketStatus::Closed
+        };
         self.markets(market_id).set(&market);
         
-        self.process_unmatched_bets(market_id);
-        self.market_closed_event(
-            market_id,
-            self.blockchain().get_block_timestamp()
-        );
+        self.process_unmatched_bets(market_id, going_in_play);
+
+        let timestamp = self.blockchain().get_block_timestamp();
+        if going_in_play {
+            self.market_in_play_event(market_id, timestamp);
+        } else {
+            self.market_closed_event(market_id, timestamp);
+        }
     }
 
-    fn process_unmatched_bets(&self, market_id: u64) {
+    /// Lapses the unmatched part of every resting order. When the market goes
+    /// in-play, orders placed with `KeepInPlay` stay in the book.
+    fn process_unmatched_bets(&self, market_id: u64, going_in_play: bool) {
         let market = self.markets(market_id).get();
         
         for selection in market.selections.iter() {
-            let back_levels = self.selection_back_levels(market_id, selection.id).get();
-            for level in back_levels.iter() {
-                for bet_nonce in level.bet_nonces.iter() {
-                    self.process_unmatched_bet(bet_nonce);
-                }
-            }
-
-            let lay_levels = self.selection_lay_levels(market_id, selection.id).get();
-            for level in lay_levels.iter() {
-                for bet_nonce in level.bet_nonces.iter() {
-                    self.process_unmatched_bet(bet_nonce);
+            for side in [BetType::Back, BetType::Lay] {
+                let odds_index = self.selection_level_odds(market_id, selection.id, side);
+                for level_node in odds_index.iter() {
+                    let odds = level_node.into_value();
+                    let queue = self.level_queue(market_id, selection.id, side, &odds);
+                    for queue_node in queue.iter() {
+                        let bet_nonce = queue_node.into_value();
+                        self.process_unmatched_bet(bet_nonce, going_in_play);
+                    }
                 }
             }
-
-            self.selection_back_liquidity(market_id, selection.id).set(&BigUint::zero());
-            self.selection_lay_liquidity(market_id, selection.id).set(&BigUint::zero());
         }
     }
 
-    fn process_unmatched_bet(&self, bet_nonce: u64) {
-        let mut bet = self.bet_by_id(bet_nonce).get();
-        
-        if bet.unmatched_amount > BigUint::zero() {
-            let refund_amount = bet.unmatched_amount.clone();
-            
-            self.send().direct(
-                &bet.bettor,
-                &bet.payment_token,
-                bet.payment_nonce,
-                &refund_amount,
-            );
-            
-            let original_matched = bet.matched_amount.clone();
-            bet.unmatched_amount = BigUint::zero();
-            bet.matched_amount = original_matched.clone();
-            // Actualizăm stake_amount să reflecte doar partea matched
-            bet.stake_amount = original_matched; 
-            
-            bet.status = if bet.matched_amount > BigUint::zero() {
-                BetStatus::Matched
-            } else {
-                BetStatus::Canceled
-            };
-            
-            self.bet_by_id(bet_nonce).set(&bet);
-            self.bet_refunded_event(bet_nonce, &bet.bettor, &refund_amount);
+    fn process_unmatched_bet(&self, bet_nonce: u64, going_in_play: bool) {
+        if going_in_play && self.bet_by_id(bet_nonce).get().persistence == BetPersistence::KeepInPlay {
+            return;
         }
+
+        self.lapse_unmatched(bet_nonce);
     }
 
+    /// Settles the event's market of a catalogue type from the final score.
     #[only_owner]
     #[endpoint(setMarketResult)]
     fn set_market_result(
@@ -82,153 +75,477 @@ pub trait FundModule:
         score_home: u32,
         score_away: u32
     ) {
+        self.require_odds_not_migrating();
         let market_id = self.get_market_id(event_id, market_type_id);
         let mut market = self.markets(market_id).get();
         
         require!(market.market_status == MarketStatus::Closed, "Market not closed");
         
-        let market_type = MarketType::from_u64(market_type_id);
-        let winning_selection = self.determine_winner(market_type, score_home, score_away);
-        
-        self.winning_selection(market_id).set(winning_selection);
-        self.current_processing_index(market_id).set(0u64);
-        
+        let market_type = self.get_market_type(market_type_id);
+        let winners = self.resolve_market_type(&market_type, score_home, score_away, None);
+        self.settle_market(&mut market, winners);
+    }
+
+    /// Settles a market created from the catalogue with the resolver of its type.
+    /// Half-time/Full-time markets also need the half-time score.
+    #[only_owner]
+    #[endpoint(setMarketScore)]
+    fn set_market_score(
+        &self,
+        market_id: u64,
+        score_home: u32,
+        score_away: u32,
+        half_time: OptionalValue<MultiValue2<u32, u32>>
+    ) {
+        self.require_odds_not_migrating();
+        require!(!self.markets(market_id).is_empty(), "Invalid market");
+        let mut market = self.markets(market_id).get();
+        require!(market.market_status == MarketStatus::Closed, "Market not closed");
+
+        let market_type_id = self.market_type_of(market_id).get();
+        require!(market_type_id != 0, "Market has no type");
+
+        let market_type = self.get_market_type(market_type_id);
+        let half_time = half_time.into_option().map(|score| score.into_tuple());
+        let winners = self.resolve_market_type(&market_type, score_home, score_away, half_time);
+        self.settle_market(&mut market, winners);
+    }
+
+    /// Settles a market from the outcome of each selection that did not lose:
+    /// the selection, the share of its stakes that wins and the share returned,
+    /// in basis points. Two winners at 5_000/0 make a dead-heat, and an Asian
+    /// handicap half-win is 5_000/5_000.
+    #[only_owner]
+    #[endpoint(setMarketResults)]
+    fn set_market_results(&self, market_id: u64, results: MultiValueEncoded<MultiValue3<u64, u64, u64>>) {
+        self.require_odds_not_migrating();
+        require!(!self.markets(market_id).is_empty(), "Invalid market");
+        let mut market = self.markets(market_id).get();
+        require!(market.market_status == MarketStatus::Closed, "Market not closed");
+
+        let mut winners = ManagedVec::<Self::Api, WinningSelection>::new();
+        for result in results.into_iter() {
+            let (selection_id, win_fraction_bps, void_fraction_bps) = result.into_tuple();
+            self.validate_selection(market_id, selection_id);
+            let settled_fraction = win_fraction_bps + void_fraction_bps;
+            require!(settled_fraction > 0 && settled_fraction <= BASIS_POINTS, "Invalid outcome fraction");
+            require!(
+                !winners.iter().any(|winner| winner.selection_id == selection_id),
+                "Duplicate winning selection"
+            );
+            winners.push(WinningSelection { selection_id, win_fraction_bps, void_fraction_bps });
+        }
+        require!(!winners.is_empty(), "No winning selection");
+
+        self.settle_market(&mut market, winners);
+    }
+
+    fn settle_market(&self, market: &mut Market<Self::Api>, winners: ManagedVec<WinningSelection>) {
+        self.winning_selections(market.market_id).set(winners);
+        self.current_processing_index(market.market_id).set(0u64);
+
         market.market_status = MarketStatus::Settled;
+        self.markets(market.market_id).set(&*market);
+    }
+
+    /// Markets settled by the first version kept a single, fully winning selection.
+    fn get_winning_selections(&self, market_id: u64) -> ManagedVec<WinningSelection> {
+        if !self.winning_selections(market_id).is_empty() {
+            return self.winning_selections(market_id).get();
+        }
+
+        let mut winners = ManagedVec::new();
+        if !self.legacy_winning_selection(market_id).is_empty() {
+            winners.push(WinningSelection {
+                selection_id: self.legacy_winning_selection(market_id).get(),
+                win_fraction_bps: BASIS_POINTS,
+                void_fraction_bps: 0,
+            });
+        }
+        winners
+    }
+
+    /// Shares of a stake on the selection that win and that are returned, in basis points.
+    fn get_outcome_fractions(&self, market_id: u64, selection_id: u64) -> (u64, u64) {
+        self.get_winning_selections(market_id)
+            .iter()
+            .find(|winner| winner.selection_id == selection_id)
+            .map_or((0, 0), |winner| (winner.win_fraction_bps, winner.void_fraction_bps))
+    }
+
+    /// Voids a market whose result cannot be given: unmatched orders lapse now,
+    /// and `processBatchBets` then returns the collateral of every matched bet.
+    #[endpoint(voidMarket)]
+    fn void_market(&self, market_id: u64, reason: ManagedBuffer) {
+        self.require_owner_or_oracle();
+        self.require_odds_not_migrating();
+        require!(!self.markets(market_id).is_empty(), "Invalid market");
+        let mut market = self.markets(market_id).get();
+        require!(
+            market.market_status != MarketStatus::Paid && market.market_status != MarketStatus::Voided,
+            "Market already finished"
+        );
+        require!(
+            self.current_processing_index(market_id).get() == 0,
+            "Market settlement already started"
+        );
+
+        self.process_unmatched_bets(market_id, false);
+
+        market.market_status = MarketStatus::Voided;
         self.markets(market_id).set(&market);
+        self.void_reason(market_id).set(&reason);
+        self.market_voided_event(market_id, self.blockchain().get_block_timestamp(), &reason);
     }
 
+    /// Settles the next `batch_size` bets of the market, resuming from the saved
+    /// cursor. Bets already settled are skipped. After the bets, the commission on
+    /// each bettor's net winnings is charged, and then the market becomes Paid.
+    /// On a voided market every bet gets its collateral back instead.
     #[endpoint(processBatchBets)]
     fn process_batch_bets(
         &self,
         market_id: u64,
         batch_size: u64
     ) -> ProcessingStatus {
-        let market = self.markets(market_id).get();
+        self.require_odds_not_migrating();
+        let mut market = self.markets(market_id).get();
+        if market.market_status == MarketStatus::Paid {
+            return ProcessingStatus::Completed;
+        }
+        if market.market_status == MarketStatus::Voided {
+            return self.process_voided_bets(market_id, batch_size);
+        }
         require!(
             market.market_status == MarketStatus::Settled,
             "Market not settled"
         );
 
-        let winning_selection = self.winning_selection(market_id).get();
-        let mut processed_count = 0u64;
+        let bet_ids = self.market_bet_ids(market_id);
+        let total_bets = bet_ids.len() as u64;
+        let mut cursor = self.current_processing_index(market_id).get();
+        let batch_end = core::cmp::min(cursor + batch_size, total_bets);
+        let mut budget = batch_size - (batch_end - cursor);
 
-        for bet_id in self.market_bet_ids(market_id).iter() {
-            if processed_count >= batch_size {
-                return ProcessingStatus::InProgress;
-            }
+        while cursor < batch_end {
+            // UnorderedSetMapper e indexat de la 1
+            cursor += 1;
+            self.settle_bet(bet_ids.get_by_index(cursor as usize));
+        }
+        self.current_processing_index(market_id).set(cursor);
 
-            let mut bet = self.bet_by_id(bet_id).get();
-            if bet.matched_amount > BigUint::zero() {
-                match bet.bet_type {
-                    BetType::Back => {
-                        if bet.selection.id == winning_selection {
-                            self.process_winning_bet(&mut bet);
-                        } else {
-                            bet.status = BetStatus::Lost;
-                        }
-                    },
-                    BetType::Lay => {
-                        if bet.selection.id != winning_selection {
-                            self.process_winning_bet(&mut bet);
-                        } else {
-                            bet.status = BetStatus::Lost;
-                        }
-                    }
-                }
-                self.bet_by_id(bet_id).set(&bet);
-                processed_count += 1;
-            }
+        if cursor < total_bets {
+            return ProcessingStatus::InProgress;
         }
+
+        // Comisionul se calculează abia după ce toate pariurile userului sunt decontate
+        let bettors = self.market_bettors(market_id);
+        let mut fee_cursor = self.fee_processing_index(market_id).get();
+        while fee_cursor < bettors.len() as u64 && budget > 0 {
+            fee_cursor += 1;
+            budget -= 1;
+            self.charge_commission(&bettors.get_by_index(fee_cursor as usize), market_id, &market.token);
+        }
+        self.fee_processing_index(market_id).set(fee_cursor);
+
+        if fee_cursor < bettors.len() as u64 {
+            return ProcessingStatus::InProgress;
+        }
+
+        market.market_status = MarketStatus::Paid;
+        self.markets(market_id).set(&market);
+        let winners = self.get_winning_selections(market_id);
+        let winning_selection = if winners.is_empty() { 0 } else { winners.get(0).selection_id };
+        self.market_settled_event(market_id, winning_selection, total_bets);
+        self.market_winners_event(market_id, &winners);
         ProcessingStatus::Completed
     }
 
-    fn process_winning_bet(&self, bet: &mut Bet<Self::Api>) {
-        bet.status = BetStatus::Win;
-        
-        let payout = match bet.bet_type {
-            BetType::Back => {
-                let mut total_payout = BigUint::zero();
-                
-                for part in bet.matched_parts.iter() {
-                    let part_profit = (part.odds.clone() - BigUint::from(100u32)) * &part.amount / BigUint::from(100u32);
-                    total_payout += &part.amount + &part_profit;
-                }
-                
-                total_payout
-            },
-            BetType::Lay => {
-                // Pentru Lay: suma tuturor părților matched
-                let mut total_matched = BigUint::zero();
-                for part in bet.matched_parts.iter() {
-                    total_matched += &part.amount;
-                }
-                total_matched
+    fn process_voided_bets(&self, market_id: u64, batch_size: u64) -> ProcessingStatus {
+        let bet_ids = self.market_bet_ids(market_id);
+        let total_bets = bet_ids.len() as u64;
+        let mut cursor = self.current_processing_index(market_id).get();
+        let batch_end = core::cmp::min(cursor + batch_size, total_bets);
+
+        while cursor < batch_end {
+            cursor += 1;
+            self.void_bet(bet_ids.get_by_index(cursor as usize));
+        }
+        self.current_processing_index(market_id).set(cursor);
+
+        if cursor < total_bets {
+            ProcessingStatus::InProgress
+        } else {
+            ProcessingStatus::Completed
+        }
+    }
+
+    /// Returns the whole collateral of a matched bet to the bettor's balance.
+    fn void_bet(&self, bet_id: u64) {
+        if self.bet_settled(bet_id).get() {
+            return;
+        }
+        self.bet_settled(bet_id).set(true);
+
+        let mut bet = self.bet_by_id(bet_id).get();
+        if bet.matched_amount == BigUint::zero() {
+            return;
+        }
+
+        let collateral = self.locked_collateral(&bet);
+        self.remove_exposure(&bet);
+        self.release_to_balance(&bet.bettor, &bet.payment_token, &collateral);
+        bet.status = BetStatus::Canceled;
+        self.count_settled_bet(bet.event, &bet.status);
+        self.index_user_bet(&bet);
+        self.bet_by_id(bet_id).set(&bet);
+        self.bet_refunded_event(bet_id, &bet.bettor, &collateral);
+    }
+
+    fn settle_bet(&self, bet_id: u64) {
+        if self.bet_settled(bet_id).get() {
+            return;
+        }
+        self.bet_settled(bet_id).set(true);
+
+        let mut bet = self.bet_by_id(bet_id).get();
+        if bet.matched_amount == BigUint::zero() {
+            return;
+        }
+
+        // Odată decis rezultatul, pariul nu mai contează la expunere, iar colateralul
+        // fie intră în plata câștigătorului, fie trece la contrapartidă
+        let collateral = self.locked_collateral(&bet);
+        self.remove_exposure(&bet);
+        self.unlock_funds(&bet.bettor, &bet.payment_token, &collateral);
+        let (win_fraction, void_fraction) = self.get_outcome_fractions(bet.event, bet.selection.id);
+
+        let payout = self.process_winning_bet(&mut bet, win_fraction, void_fraction);
+        self.count_settled_bet(bet.event, &bet.status);
+        self.index_user_bet(&bet);
+        if payout > BigUint::zero() {
+            self.user_market_winning_bets(&bet.bettor, bet.event).push(&bet.id);
+        }
+        let result = BigInt::from(payout) - BigInt::from(collateral);
+        self.user_market_result(&bet.bettor, bet.event).update(|total| *total += result);
+        self.market_bettors(bet.event).insert(bet.bettor.clone());
+        self.bet_by_id(bet_id).set(&bet);
+    }
+
+    /// Charges the commission on the bettor's net winnings on the market, taken
+    /// out of the recorded payouts of their winning bets.
+    fn charge_commission(&self, bettor: &ManagedAddress, market_id: u64, token_identifier: &EgldOrEsdtTokenIdentifier) {
+        let result = self.user_market_result(bettor, market_id).take();
+        // Un rezultat negativ nu plătește comision
+        let net_winnings = match result.into_big_uint().into_option() {
+            Some(net_winnings) => net_winnings,
+            None => return,
+        };
+        let fee = net_winnings * self.get_commission_rate(bettor.clone(), market_id) / BASIS_POINTS;
+        if fee == BigUint::zero() {
+            return;
+        }
+
+        let mut remaining_fee = fee.clone();
+        for bet_id in self.user_market_winning_bets(bettor, market_id).iter() {
+            if remaining_fee == BigUint::zero() {
+                break;
             }
+            let payout = self.bet_payout(bet_id).get();
+            let deducted = core::cmp::min(payout.clone(), remaining_fee.clone());
+            self.bet_payout(bet_id).set(payout - &deducted);
+            remaining_fee -= deducted;
+        }
+
+        self.total_unclaimed(token_identifier).update(|total| *total -= &fee);
+        self.fee_treasury(token_identifier).update(|total| *total += &fee);
+        self.fee_charged_event(bettor, market_id, token_identifier, &fee);
+    }
+
+    /// Records what the bet collects when its selection wins `win_fraction` of
+    /// the stakes and returns `void_fraction` of them. Each matched part holds
+    /// the backer's stake and the layer's liability: the backer takes the
+    /// winning share of it plus the returned share of the stake, the layer the rest.
+    fn process_winning_bet(&self, bet: &mut Bet<Self::Api>, win_fraction: u64, void_fraction: u64) -> BigUint {
+        let mut payout = BigUint::zero();
+        for part in bet.matched_parts.iter() {
+            let pot = &part.amount + &self.calculate_liability(&part.amount, &part.odds);
+            let backer_share = &pot * win_fraction / BASIS_POINTS + &part.amount * void_fraction / BASIS_POINTS;
+            payout += match bet.bet_type {
+                BetType::Back => backer_share,
+                BetType::Lay => pot - backer_share,
+            };
+        }
+
+        // Pentru Lay, partea câștigată de backer e cea pierdută de layer
+        let lost_fraction = BASIS_POINTS - win_fraction - void_fraction;
+        bet.status = match bet.bet_type {
+            BetType::Back => BetStatus::from_outcome(win_fraction, void_fraction, lost_fraction),
+            BetType::Lay => BetStatus::from_outcome(lost_fraction, void_fraction, win_fraction),
         };
+        if payout == BigUint::zero() {
+            return payout;
+        }
         
-        self.send().direct(
-            &bet.bettor,
-            &bet.payment_token,
-            bet.payment_nonce,
-            &payout
-        );
-    
-        self.reward_distributed_event(
-            bet.nft_nonce,
-            &bet.bettor,
-            &payout
+        // Plata se face doar la claim, ca un destinatar problematic să nu blocheze decontarea
+        self.bet_payout(bet.id).set(&payout);
+        self.total_unclaimed(&bet.payment_token).update(|total| *total += &payout);
+        payout
+    }
+
+    /// Pays out the recorded winnings of the given bets. The caller is either the
+    /// bettor or presents the ticket of a single bet, which is sent back with the payout.
+    #[payable("*")]
+    #[endpoint(claimWinnings)]
+    fn claim_winnings(&self, bet_ids: MultiValueEncoded<u64>) {
+        let caller = self.blockchain().get_caller();
+        for bet_id in bet_ids.into_iter() {
+            let bet = self.require_valid_bet_nft(bet_id);
+            let payout = self.take_winnings(&bet);
+            self.send().direct(&caller, &bet.payment_token, bet.payment_nonce, &payout);
+            self.reward_distributed_event(bet.id, &caller, &payout);
+        }
+
+        let tickets = self.call_value().all_esdt_transfers().clone_value();
+        if !tickets.is_empty() {
+            self.send().direct_multi(&caller, &tickets);
+        }
+    }
+
+    /// Pays out every unclaimed winning bet of the caller on the market.
+    #[endpoint(claimAll)]
+    fn claim_all(&self, market_id: u64) -> BigUint {
+        let caller = self.blockchain().get_caller();
+        let market = self.markets(market_id).get();
+
+        let mut total_payout = BigUint::zero();
+        for bet_id in self.user_market_winning_bets(&caller, market_id).iter() {
+            // Comisionul poate lăsa un pariu câștigător fără nimic de plată
+            if self.bet_claimed(bet_id).get() || self.bet_payout(bet_id).get() == 0 {
+                continue;
+            }
+
+            let bet = self.bet_by_id(bet_id).get();
+            let payout = self.take_winnings(&bet);
+            self.reward_distributed_event(bet.id, &caller, &payout);
+            total_payout += payout;
+        }
+        require!(total_payout > BigUint::zero(), "Nothing to claim");
+
+        self.send().direct(&caller, &market.token, 0, &total_payout);
+        total_payout
+    }
+
+    fn take_winnings(&self, bet: &Bet<Self::Api>) -> BigUint {
+        require!(
+            self.markets(bet.event).get().market_status == MarketStatus::Paid,
+            "Market settlement not finished"
         );
+        require!(!self.bet_claimed(bet.id).get(), "Winnings already claimed");
+        let payout = self.bet_payout(bet.id).get();
+        require!(payout > BigUint::zero(), "Nothing to claim");
+
+        self.bet_claimed(bet.id).set(true);
+        self.total_unclaimed(&bet.payment_token).update(|total| *total -= &payout);
+        payout
     }
 
-    #[inline]
-    fn get_market_id(&self, event_id: u64, market_type_id: u64) -> u64 {
-        let markets = self.markets_by_event(event_id).get();
-        require!(!markets.is_empty(), "Invalid market");
-        markets.get(market_type_id as usize - 1)
+    #[only_owner]
+    #[endpoint(setCommissionRate)]
+    fn set_commission_rate(&self, rate_bps: u64) {
+        require!(rate_bps <= BASIS_POINTS, "Invalid commission rate");
+        self.commission_rate().set(rate_bps);
     }
 
-    fn determine_winner(
-        &self,
-        market_type: MarketType,
-        score_home: u32,
-        score_away: u32
-    ) -> u64 {
-        match market_type {
-            MarketType::FullTimeResult => {
-                if score_home > score_away { 1u64 }
-                else if score_home < score_away { 2u64 }
-                else { 3u64 }
-            },
-            MarketType::TotalGoals => {
-                if score_home + score_away > 2 { 1u64 }
-                else { 2u64 }
+    /// Overrides the global rate on one market; without a rate the override is cleared.
+    #[only_owner]
+    #[endpoint(setMarketCommissionRate)]
+    fn set_market_commission_rate(&self, market_id: u64, rate_bps: OptionalValue<u64>) {
+        require!(!self.markets(market_id).is_empty(), "Invalid market");
+        match rate_bps {
+            OptionalValue::Some(rate_bps) => {
+                require!(rate_bps <= BASIS_POINTS, "Invalid commission rate");
+                self.market_commission_rate(market_id).set(rate_bps);
             },
-            MarketType::BothTeamsToScore => {
-                if score_home > 0 && score_away > 0 { 1u64 }
-                else { 2u64 }
-            }
+            OptionalValue::None => self.market_commission_rate(market_id).clear(),
         }
     }
 
-    // View functions
-    #[view(getWinningSelection)]
-    fn get_winning_selection(&self, market_id: u64) -> u64 {
-        self.winning_selection(market_id).get()
+    #[only_owner]
+    #[endpoint(setDiscountTier)]
+    fn set_discount_tier(&self, tier: u8, discount_bps: u64) {
+        require!(discount_bps <= BASIS_POINTS, "Invalid discount");
+        self.discount_tier(tier).set(discount_bps);
+    }
+
+    /// Puts the user in a discount tier; tier 0 means no discount.
+    #[only_owner]
+    #[endpoint(setUserDiscountTier)]
+    fn set_user_discount_tier(&self, user: ManagedAddress, tier: u8) {
+        self.user_discount_tier(&user).set(tier);
+    }
+
+    /// Sends the collected fees in the token to the owner, all of them when no
+    /// amount is given.
+    #[only_owner]
+    #[endpoint(withdrawFees)]
+    fn withdraw_fees(&self, token_identifier: EgldOrEsdtTokenIdentifier, amount: OptionalValue<BigUint>) {
+        let treasury = self.fee_treasury(&token_identifier).get();
+        let amount = amount.into_option().unwrap_or(treasury.clone());
+        require!(amount > BigUint::zero() && amount <= treasury, "Invalid amount");
+
+        self.fee_treasury(&token_identifier).set(treasury - &amount);
+        let owner = self.blockchain().get_owner_address();
+        self.send().direct(&owner, &token_identifier, 0, &amount);
+    }
+
+    /// Commission rate in basis points the user pays on the market, after the
+    /// discount of their tier.
+    #[view(getCommissionRate)]
+    fn get_commission_rate(&self, user: ManagedAddress, market_id: u64) -> u64 {
+        let rate = if self.market_commission_rate(market_id).is_empty() {
+            self.commission_rate().get()
+        } else {
+            self.market_commission_rate(market_id).get()
+        };
+        let tier = self.user_discount_tier(&user).get();
+        let discount = if tier == 0 { 0 } else { self.discount_tier(tier).get() };
+
+        rate * (BASIS_POINTS - discount) / BASIS_POINTS
+    }
+
+    #[view(getFeeTreasury)]
+    fn get_fee_treasury(&self, token_identifier: EgldOrEsdtTokenIdentifier) -> BigUint {
+        self.fee_treasury(&token_identifier).get()
+    }
+
+    /// Recorded payout of the bet and whether it was claimed.
+    #[view(getBetPayout)]
+    fn get_bet_payout(&self, bet_id: u64) -> MultiValue2<BigUint, bool> {
+        (self.bet_payout(bet_id).get(), self.bet_claimed(bet_id).get()).into()
     }
 
+    /// The event's market created from catalogue type `market_type_id`. Markets
+    /// created without a type are settled by id through `setMarketResults`.
+    fn get_market_id(&self, event_id: u64, market_type_id: u64) -> u64 {
+        require!(market_type_id != 0, "Invalid market type");
+        self.markets_by_event(event_id)
+            .get()
+            .iter()
+            .find(|market_id| self.market_type_of(*market_id).get() == market_type_id)
+            .unwrap_or_else(|| sc_panic!("No market of this type on the event"))
+    }
+
+    // View functions
+    /// Winning selections of a settled market with their dead-heat fractions,
+    /// and the market status.
     #[view(getMarketSettlementDetails)]
     fn get_market_settlement_details(
         &self,
         market_id: u64
-    ) -> (u64, MarketStatus) {
+    ) -> (ManagedVec<WinningSelection>, MarketStatus) {
         let market = self.markets(market_id).get();
-        let winning_selection = if self.winning_selection(market_id).is_empty() {
-            0u64
-        } else {
-            self.winning_selection(market_id).get()
-        };
-        
-        (winning_selection, market.market_status)
+        (self.get_winning_selections(market_id), market.market_status)
     }
 
     #[view(getBetStatusDetails)]
@@ -242,20 +559,21 @@ pub trait FundModule:
 
     #[view(getProcessingProgress)]
     fn get_processing_progress(&self, market_id: u64) -> ProcessingProgress {
-        let current_index = if self.current_processing_index(market_id).is_empty() {
-            0u64
-        } else {
-            self.current_processing_index(market_id).get()
+        let market = self.markets(market_id).get();
+        let processed_bets = self.current_processing_index(market_id).get();
+        let total_bets = self.market_bet_ids(market_id).len() as u64;
+        let status = match market.market_status {
+            MarketStatus::Paid => ProcessingStatus::Completed,
+            MarketStatus::Voided if processed_bets == total_bets => ProcessingStatus::Completed,
+            MarketStatus::Settled | MarketStatus::Voided => ProcessingStatus::InProgress,
+            _ => ProcessingStatus::NotStarted,
         };
 
         ProcessingProgress {
             market_id,
-            processed_bets: current_index,
-            status: if current_index > 0 { 
-                ProcessingStatus::InProgress 
-            } else { 
-                ProcessingStatus::Completed 
-            }
+            processed_bets,
+            total_bets,
+            status
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+#[multiversx_sc::module]
+pub trait LedgerModule:
+    crate::storage::StorageModule
+    + crate::events::EventsModule
+{
+    /// Credits the attached payment to the caller's balance.
+    #[payable("*")]
+    #[endpoint(deposit)]
+    fn deposit(&self) {
+        let caller = self.blockchain().get_caller();
+        let (token_identifier, token_nonce, amount) = self.call_value().egld_or_single_esdt().into_tuple();
+        require!(self.accepted_tokens().contains(&token_identifier), "Token not accepted");
+        require!(token_nonce == 0, "Invalid payment token nonce");
+        require!(amount > BigUint::zero(), "Amount must be greater than zero");
+
+        self.credit_balance(&caller, &token_identifier, &amount);
+        self.deposit_event(&caller, &token_identifier, &amount);
+    }
+
+    /// Sends `amount` of the caller's available balance back to the wallet.
+    #[endpoint(withdraw)]
+    fn withdraw(&self, token_identifier: EgldOrEsdtTokenIdentifier, amount: BigUint) {
+        let caller = self.blockchain().get_caller();
+        require!(amount > BigUint::zero(), "Amount must be greater than zero");
+
+        self.debit_balance(&caller, &token_identifier, &amount);
+        self.send().direct(&caller, &token_identifier, 0, &amount);
+        self.withdraw_event(&caller, &token_identifier, &amount);
+    }
+
+    fn credit_balance(&self, user: &ManagedAddress, token_identifier: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        if amount == &BigUint::zero() {
+            return;
+        }
+        self.user_balance(user, token_identifier).update(|balance| *balance += amount);
+        self.total_user_balances(token_identifier).update(|total| *total += amount);
+    }
+
+    fn debit_balance(&self, user: &ManagedAddress, token_identifier: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        let balance_mapper = self.user_balance(user, token_identifier);
+        let balance = balance_mapper.get();
+        require!(&balance >= amount, "Insufficient balance");
+        balance_mapper.set(balance - amount);
+        self.total_user_balances(token_identifier).update(|total| *total -= amount);
+    }
+
+    fn lock_funds(&self, user: &ManagedAddress, token_identifier: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        self.locked_funds(user, token_identifier).update(|locked| *locked += amount);
+        self.total_locked(token_identifier).update(|total| *total += amount);
+    }
+
+    fn unlock_funds(&self, user: &ManagedAddress, token_identifier: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        self.locked_funds(user, token_identifier).update(|locked| *locked -= amount);
+        self.total_locked(token_identifier).update(|total| *total -= amount);
+    }
+
+    /// Moves collateral no longer at risk from the reserved part to the available one.
+    fn release_to_balance(&self, user: &ManagedAddress, token_identifier: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        self.unlock_funds(user, token_identifier, amount);
+        self.credit_balance(user, token_identifier, amount);
+    }
+
+    /// Available and reserved balance of the user in the token.
+    #[view(getBalance)]
+    fn get_balance(
+        &self,
+        user: ManagedAddress,
+        token_identifier: EgldOrEsdtTokenIdentifier
+    ) -> MultiValue2<BigUint, BigUint> {
+        (
+            self.user_balance(&user, &token_identifier).get(),
+            self.locked_funds(&user, &token_identifier).get()
+        ).into()
+    }
+
+    /// The contract's balance in the token, what it owes (collateral, available
+    /// balances, unclaimed winnings and collected fees) and whether the first covers the second.
+    #[view(getSolvency)]
+    fn get_solvency(&self, token_identifier: EgldOrEsdtTokenIdentifier) -> MultiValue3<BigUint, BigUint, bool> {
+        let contract_balance = self.blockchain().get_sc_balance(&token_identifier, 0);
+        let obligations = self.total_locked(&token_identifier).get()
+            + self.total_user_balances(&token_identifier).get()
+            + self.total_unclaimed(&token_identifier).get()
+            + self.fee_treasury(&token_identifier).get();
+        let solvent = contract_balance >= obligations;
+
+        (contract_balance, obligations, solvent).into()
+    }
+}
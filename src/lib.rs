@@ -10,6 +10,11 @@ pub mod fund;
 pub mod market;
 pub mod validation;
 pub mod tracker;
+pub mod odds;
+pub mod exposure;
+pub mod ledger;
+pub mod catalogue;
+pub mod migration;
 pub mod types;
 
 multiversx_sc::imports!();
@@ -23,12 +28,26 @@ storage::StorageModule
 + bet::BetModule
 + market::MarketModule
 + tracker::TrackerModule
-+ validation::ValidationModule{
++ validation::ValidationModule
++ odds::OddsModule
++ exposure::ExposureModule
++ ledger::LedgerModule
++ catalogue::CatalogueModule
++ migration::MigrationModule{
     #[upgrade]
-    fn upgrade(&self) {}
+    fn upgrade(&self) {
+        self.start_legacy_migration();
+        self.upgrade_bet_ids();
+        self.upgrade_odds();
+        self.init_self_trade_prevention();
+        self.init_market_types();
+    }
 
     #[init]
     fn init(&self) {
         self.market_counter().set(0);
+        self.init_odds();
+        self.init_self_trade_prevention();
+        self.init_market_types();
     }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::types::{Market, MarketStatus, Selection, Tracker};
+use crate::types::{Event, EventStatus, Market, MarketStatus, Selection, StakeLimits};
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
@@ -9,8 +9,62 @@ pub trait MarketModule:
     crate::fund::FundModule +
     crate::nft::NftModule +
     crate::tracker::TrackerModule +
-    crate::validation::ValidationModule
+    crate::validation::ValidationModule +
+    crate::odds::OddsModule +
+    crate::exposure::ExposureModule +
+    crate::ledger::LedgerModule +
+    crate::catalogue::CatalogueModule
 {
+    /// Registers an event, indexed by its start time and its competition.
+    #[only_owner]
+    #[endpoint(createEvent)]
+    fn create_event(
+        &self,
+        sport: ManagedBuffer,
+        competition: ManagedBuffer,
+        home: ManagedBuffer,
+        away: ManagedBuffer,
+        start_timestamp: u64
+    ) -> u64 {
+        self.require_odds_not_migrating();
+        require!(
+            start_timestamp > self.blockchain().get_block_timestamp(),
+            "Invalid event timestamp"
+        );
+        require!(home != away, "Home and away must differ");
+
+        let event_id = self.event_counter().get() + 1;
+        self.event_counter().set(event_id);
+
+        self.events_by_timestamp(start_timestamp).update(|events| events.push(event_id));
+        self.events_by_competition(&competition).update(|events| events.push(event_id));
+        self.event_created_event(event_id, &competition, start_timestamp);
+
+        self.events(event_id).set(Event {
+            event_id,
+            sport,
+            competition,
+            home,
+            away,
+            start_timestamp,
+            status: EventStatus::Scheduled,
+        });
+
+        event_id
+    }
+
+    #[only_owner]
+    #[endpoint(setEventStatus)]
+    fn set_event_status(&self, event_id: u64, status: EventStatus) {
+        require!(!self.events(event_id).is_empty(), "Invalid event");
+        self.events(event_id).update(|event| {
+            require!(!event.status.is_final(), "Event already finished");
+            require!(event.status.can_move_to(&status), "Invalid event status transition");
+            event.status = status;
+        });
+        self.event_status_changed_event(event_id, status);
+    }
+
     #[only_owner]
     #[endpoint(createMarket)]
     fn create_market(
@@ -18,9 +72,13 @@ pub trait MarketModule:
         event_id: u64,
         description: ManagedBuffer,
         selection_values: ManagedVec<u64>,
-        close_timestamp: u64
+        close_timestamp: u64,
+        token: EgldOrEsdtTokenIdentifier,
+        in_play: OptionalValue<bool>
     ) -> u64 {
-        self.validate_market_creation(close_timestamp);
+        self.require_odds_not_migrating();
+        self.validate_market_creation(event_id, close_timestamp);
+        require!(self.accepted_tokens().contains(&token), "Token not accepted");
         
         let market_id = self.get_next_market_id();
         let selections = self.create_selections(market_id, selection_values);
@@ -32,8 +90,9 @@ pub trait MarketModule:
             selections,
             liquidity: BigUint::zero(),
             close_timestamp,
+            token,
             market_status: MarketStatus::Open,
-            total_matched_amount: BigUint::zero(),
+            in_play: in_play.into_option().unwrap_or(false),
             created_at: self.blockchain().get_block_timestamp(),
         };
     
@@ -48,15 +107,62 @@ pub trait MarketModule:
         market_id
     }
 
+    /// Creates a market of a catalogue type, with the selections of its resolver.
+    #[only_owner]
+    #[endpoint(createTypedMarket)]
+    fn create_typed_market(
+        &self,
+        event_id: u64,
+        market_type_id: u64,
+        close_timestamp: u64,
+        token: EgldOrEsdtTokenIdentifier,
+        in_play: OptionalValue<bool>
+    ) -> u64 {
+        let market_type = self.get_market_type(market_type_id);
+        let selection_count = self.selection_count(market_type.kind, market_type.line);
+
+        let mut selection_values = ManagedVec::new();
+        for value in 1..=selection_count {
+            selection_values.push(value);
+        }
+
+        let market_id = self.create_market(
+            event_id,
+            market_type.description,
+            selection_values,
+            close_timestamp,
+            token,
+            in_play
+        );
+        self.market_type_of(market_id).set(market_type_id);
+
+        market_id
+    }
+
+    /// Closes the open markets of the events starting at `timestamp`, once it
+    /// is reached, and marks the scheduled ones as live.
     #[endpoint(processEventMarkets)]
     fn process_event_markets(&self, timestamp: u64) {
+        require!(
+            timestamp <= self.blockchain().get_block_timestamp(),
+            "Event start not reached"
+        );
         let events = self.events_by_timestamp(timestamp).get();
         require!(!events.is_empty(), "No events found for timestamp");
 
         for event_id in events.iter() {
+            let mut event = self.events(event_id).get();
+            if event.status == EventStatus::Scheduled {
+                event.status = EventStatus::Live;
+                self.events(event_id).set(&event);
+                self.event_status_changed_event(event_id, EventStatus::Live);
+            }
+
             let market_ids = self.markets_by_event(event_id).get();
             for market_id in market_ids.iter() {
-                self.handle_expired_market(market_id);
+                if self.markets(market_id).get().market_status == MarketStatus::Open {
+                    self.handle_expired_market(market_id);
+                }
             }
         }
     }
@@ -65,15 +171,23 @@ pub trait MarketModule:
     fn process_market_close(&self, market_id: u64) {
         let market = self.markets(market_id).get();
         
-        require!(
-            market.market_status == MarketStatus::Open,
-            "Market not open"
-        );
-        
-        require!(
-            self.blockchain().get_block_timestamp() >= market.close_timestamp,
-            "Market timestamp not reached"
-        );
+        // Sfârșitul unei piețe in-play îl anunță doar owner-ul
+        if market.market_status == MarketStatus::InPlay {
+            require!(
+                self.blockchain().get_caller() == self.blockchain().get_owner_address(),
+                "Only owner can close an in-play market"
+            );
+        } else {
+            require!(
+                market.market_status == MarketStatus::Open,
+                "Market not open"
+            );
+            
+            require!(
+                self.blockchain().get_block_timestamp() >= market.close_timestamp,
+                "Market timestamp not reached"
+            );
+        }
 
         self.handle_expired_market(market_id);
     }
@@ -82,67 +196,135 @@ pub trait MarketModule:
         &self,
         market_id: u64,
         descriptions: ManagedVec<u64>
-    ) -> ManagedVec<Selection<Self::Api>> {
+    ) -> ManagedVec<Selection> {
         let mut selections = ManagedVec::new();
         for (index, value) in descriptions.iter().enumerate() {
             let id = (index + 1) as u64;
             self.init_selection_storage(market_id, id);
-            let tracker = self.selection_tracker(market_id, id).get();
             selections.push(Selection {
                 id,
-                value: value,
-                priority_queue: tracker,
+                value,
             });
         }
         selections
     }
 
     fn init_selection_storage(&self, market_id: u64, selection_id: u64) {
-        let tracker = Tracker {
-            back_levels: ManagedVec::new(),
-            lay_levels: ManagedVec::new(),
-            back_liquidity: BigUint::zero(),
-            lay_liquidity: BigUint::zero(),
-            matched_count: 0,
-            unmatched_count: 0,
-            partially_matched_count: 0,
-            win_count: 0,
-            lost_count: 0,
-            canceled_count: 0,
-        };
-
-        self.selection_tracker(market_id, selection_id).set(&tracker);
-
-        self.selection_back_levels(market_id, selection_id)
-            .set(&ManagedVec::new());
-        self.selection_lay_levels(market_id, selection_id)
-            .set(&ManagedVec::new());
-
         self.selection_back_liquidity(market_id, selection_id)
-            .set(&BigUint::zero());
+            .set(BigUint::zero());
         self.selection_lay_liquidity(market_id, selection_id)
-            .set(&BigUint::zero());
+            .set(BigUint::zero());
 
-        self.selection_matched_count(market_id, selection_id).set(&0u64);
-        self.selection_unmatched_count(market_id, selection_id).set(&0u64);
-        self.selection_partially_matched_count(market_id, selection_id).set(&0u64);
-        self.selection_win_count(market_id, selection_id).set(&0u64);
-        self.selection_lost_count(market_id, selection_id).set(&0u64);
-        self.selection_canceled_count(market_id, selection_id).set(&0u64);
+        self.selection_matched_count(market_id, selection_id).set(0u64);
+        self.selection_unmatched_count(market_id, selection_id).set(0u64);
+        self.selection_partially_matched_count(market_id, selection_id).set(0u64);
+        self.selection_win_count(market_id, selection_id).set(0u64);
+        self.selection_lost_count(market_id, selection_id).set(0u64);
+        self.selection_canceled_count(market_id, selection_id).set(0u64);
 
-        self.total_matched_amount(market_id, selection_id).set(&BigUint::zero());
+        self.total_matched_amount(market_id, selection_id).set(BigUint::zero());
     }
 
     fn get_selection(
         &self,
         market: &Market<Self::Api>,
         selection_id: u64
-    ) -> Selection<Self::Api> {
+    ) -> Selection {
         market.selections.iter()
             .find(|s| s.id == selection_id)
             .unwrap_or_else(|| sc_panic!("Selection not found"))
     }
 
+    #[only_owner]
+    #[endpoint(setOracle)]
+    fn set_oracle(&self, oracle: ManagedAddress) {
+        self.oracle().set(oracle);
+    }
+
+    /// Accepts a payment token with its stake bounds, in its smallest unit.
+    #[only_owner]
+    #[endpoint(addAcceptedToken)]
+    fn add_accepted_token(&self, token: EgldOrEsdtTokenIdentifier, min_stake: BigUint, max_stake: BigUint) {
+        require!(token.is_valid(), "Invalid token");
+        self.set_token_stake_limits(token.clone(), min_stake, max_stake);
+        self.accepted_tokens().insert(token);
+    }
+
+    /// Markets already created in the token keep settling in it.
+    #[only_owner]
+    #[endpoint(removeAcceptedToken)]
+    fn remove_accepted_token(&self, token: EgldOrEsdtTokenIdentifier) {
+        self.accepted_tokens().swap_remove(&token);
+    }
+
+    #[view(getAcceptedTokens)]
+    fn get_accepted_tokens(&self) -> MultiValueEncoded<EgldOrEsdtTokenIdentifier> {
+        self.accepted_tokens().iter().collect()
+    }
+
+    /// Sets the stake bounds for a payment token, in its smallest unit.
+    #[only_owner]
+    #[endpoint(setTokenStakeLimits)]
+    fn set_token_stake_limits(
+        &self,
+        token: EgldOrEsdtTokenIdentifier,
+        min_stake: BigUint,
+        max_stake: BigUint
+    ) {
+        require!(min_stake > 0 && min_stake <= max_stake, "Invalid stake limits");
+        self.token_stake_limits(&token).set(StakeLimits { min_stake, max_stake });
+    }
+
+    /// Narrows the token's stake bounds on one market. The bounds must overlap
+    /// the token's, so the market stays open to some stake.
+    #[only_owner]
+    #[endpoint(setMarketStakeLimits)]
+    fn set_market_stake_limits(
+        &self,
+        market_id: u64,
+        token: EgldOrEsdtTokenIdentifier,
+        min_stake: BigUint,
+        max_stake: BigUint
+    ) {
+        require!(!self.markets(market_id).is_empty(), "Invalid market");
+        require!(min_stake > 0 && min_stake <= max_stake, "Invalid stake limits");
+        self.market_stake_limits(market_id, &token).set(StakeLimits { min_stake, max_stake });
+        self.get_effective_stake_limits(market_id, &token);
+    }
+
+    #[only_owner]
+    #[endpoint(clearMarketStakeLimits)]
+    fn clear_market_stake_limits(&self, market_id: u64, token: EgldOrEsdtTokenIdentifier) {
+        self.market_stake_limits(market_id, &token).clear();
+    }
+
+    /// The stake bounds that apply on the market.
+    #[view(getStakeLimits)]
+    fn get_stake_limits(
+        &self,
+        market_id: u64,
+        token: EgldOrEsdtTokenIdentifier
+    ) -> MultiValue2<BigUint, BigUint> {
+        let limits = self.get_effective_stake_limits(market_id, &token);
+        (limits.min_stake, limits.max_stake).into()
+    }
+
+    #[view(getEvent)]
+    fn get_event(&self, event_id: u64) -> Event<Self::Api> {
+        require!(!self.events(event_id).is_empty(), "Invalid event");
+        self.events(event_id).get()
+    }
+
+    #[view(getEventsByTimestamp)]
+    fn get_events_by_timestamp(&self, timestamp: u64) -> ManagedVec<u64> {
+        self.events_by_timestamp(timestamp).get()
+    }
+
+    #[view(getEventsByCompetition)]
+    fn get_events_by_competition(&self, competition: ManagedBuffer) -> ManagedVec<u64> {
+        self.events_by_competition(&competition).get()
+    }
+
     #[view(getMarketStatus)]
     fn get_market_status(&self, market_id: u64) -> MarketStatus {
         self.markets(market_id).get().market_status
@@ -0,0 +1,364 @@
+use crate::constants::constants::{DEFAULT_ODDS_SCALE, LEGACY_MARKET_TYPES, LEGACY_ODDS_SCALE};
+use crate::types::{Bet, BetPersistence, BetStatus, BetType, Event, EventStatus, LegacyMarketStatus, LegacyPriceLevel, Market, MarketStatus, MatchedPart, Selection};
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+#[multiversx_sc::module]
+pub trait MigrationModule:
+    crate::storage::StorageModule
+    + crate::events::EventsModule
+    + crate::validation::ValidationModule
+    + crate::odds::OddsModule
+    + crate::exposure::ExposureModule
+    + crate::ledger::LedgerModule
+    + crate::tracker::TrackerModule
+{
+    /// A contract upgraded from the first version has no odds scale and still
+    /// holds its records in the old layout. Everything but `migrateLegacyState`
+    /// is paused until they are converted.
+    fn start_legacy_migration(&self) {
+        if !self.odds_scale().is_empty() {
+            return;
+        }
+
+        self.legacy_migration_market_cursor().set(1);
+        self.legacy_migration_bet_cursor().set(1);
+    }
+
+    /// Converts up to `batch_size` markets, then up to `batch_size` bets, to the
+    /// current layout. Can be called again until it returns true.
+    #[only_owner]
+    #[endpoint(migrateLegacyState)]
+    fn migrate_legacy_state(&self, batch_size: u64) -> bool {
+        require!(!self.legacy_migration_market_cursor().is_empty(), "No legacy migration in progress");
+        require!(batch_size > 0, "Invalid batch size");
+
+        let last_market_id = self.market_counter().get();
+        let mut market_cursor = self.legacy_migration_market_cursor().get();
+        let mut processed = 0u64;
+        while market_cursor <= last_market_id && processed < batch_size {
+            self.migrate_legacy_market(market_cursor);
+            market_cursor += 1;
+            processed += 1;
+        }
+        self.legacy_migration_market_cursor().set(market_cursor);
+
+        // Pariurile au nevoie de piețele în layout-ul nou pentru token și expunere
+        let last_bet_id = self.last_bet_id().get();
+        let mut bet_cursor = self.legacy_migration_bet_cursor().get();
+        while market_cursor > last_market_id && bet_cursor <= last_bet_id && processed < batch_size {
+            self.migrate_legacy_bet(bet_cursor);
+            bet_cursor += 1;
+            processed += 1;
+        }
+        self.legacy_migration_bet_cursor().set(bet_cursor);
+
+        if market_cursor <= last_market_id || bet_cursor <= last_bet_id {
+            return false;
+        }
+
+        self.legacy_migration_market_cursor().clear();
+        self.legacy_migration_bet_cursor().clear();
+        true
+    }
+
+    /// Rewrites the market without the order book snapshot in its selections,
+    /// with the fields added since: first-version markets never go in play and
+    /// settle in the token their bets were paid in. The catalogue type is the
+    /// one `setMarketResult` found the market by.
+    fn migrate_legacy_market(&self, market_id: u64) {
+        let legacy_mapper = self.legacy_markets(market_id);
+        if legacy_mapper.is_empty() {
+            return;
+        }
+        let legacy = legacy_mapper.get();
+
+        let mut selections = ManagedVec::new();
+        for selection in legacy.selections.iter() {
+            selections.push(Selection { id: selection.id, value: selection.value });
+            if legacy.market_status == LegacyMarketStatus::Open {
+                self.migrate_legacy_levels(market_id, selection.id, BetType::Back);
+                self.migrate_legacy_levels(market_id, selection.id, BetType::Lay);
+            }
+            self.legacy_selection_back_levels(market_id, selection.id).clear();
+            self.legacy_selection_lay_levels(market_id, selection.id).clear();
+            self.legacy_selection_tracker(market_id, selection.id).clear();
+        }
+        let market_status = match legacy.market_status {
+            LegacyMarketStatus::Open => MarketStatus::Open,
+            LegacyMarketStatus::Closed => MarketStatus::Closed,
+            LegacyMarketStatus::Settled => MarketStatus::Settled,
+        };
+
+        self.register_legacy_event(legacy.event_id, legacy.close_timestamp, &market_status);
+        self.market_total_matched(market_id).set(&legacy.total_matched_amount);
+        self.markets(market_id).set(Market {
+            market_id,
+            event_id: legacy.event_id,
+            description: legacy.description,
+            selections,
+            close_timestamp: legacy.close_timestamp,
+            token: self.get_legacy_market_token(market_id),
+            market_status,
+            in_play: false,
+            liquidity: legacy.liquidity,
+            created_at: legacy.created_at,
+        });
+
+        // A n-a piață a evenimentului era tipul n, cu aceleași selecții ca în catalog
+        let position = self.markets_by_event(legacy.event_id)
+            .get()
+            .iter()
+            .position(|event_market_id| event_market_id == market_id);
+        if let Some(index) = position {
+            let market_type_id = index as u64 + 1;
+            if market_type_id <= LEGACY_MARKET_TYPES {
+                self.market_type_of(market_id).set(market_type_id);
+            }
+        }
+    }
+
+    /// First-version markets point at event ids assigned off-chain. Each one is
+    /// registered so it can take new markets, starting when its last market
+    /// closes and as far along as its least advanced market, and new events
+    /// are numbered after the highest of them.
+    fn register_legacy_event(&self, event_id: u64, close_timestamp: u64, market_status: &MarketStatus) {
+        let status = match market_status {
+            MarketStatus::Open => EventStatus::Scheduled,
+            MarketStatus::Settled => EventStatus::Finished,
+            _ => EventStatus::Live,
+        };
+
+        let event_mapper = self.events(event_id);
+        if event_mapper.is_empty() {
+            event_mapper.set(Event {
+                event_id,
+                sport: ManagedBuffer::new(),
+                competition: ManagedBuffer::new(),
+                home: ManagedBuffer::new(),
+                away: ManagedBuffer::new(),
+                start_timestamp: close_timestamp,
+                status,
+            });
+        } else {
+            event_mapper.update(|event| {
+                if close_timestamp > event.start_timestamp {
+                    event.start_timestamp = close_timestamp;
+                }
+                if event.status == EventStatus::Finished || status == EventStatus::Scheduled {
+                    event.status = status;
+                }
+            });
+        }
+
+        if event_id > self.event_counter().get() {
+            self.event_counter().set(event_id);
+        }
+    }
+
+    /// Sets the token of a first-version market that has no bets to take it from.
+    #[only_owner]
+    #[endpoint(setLegacyMarketToken)]
+    fn set_legacy_market_token(&self, market_id: u64, token: EgldOrEsdtTokenIdentifier) {
+        require!(!self.legacy_migration_market_cursor().is_empty(), "No legacy migration in progress");
+        require!(
+            market_id >= self.legacy_migration_market_cursor().get() && market_id <= self.market_counter().get(),
+            "Invalid market"
+        );
+        require!(token.is_valid(), "Invalid token");
+        self.legacy_market_token(market_id).set(token);
+    }
+
+    /// The first version took any token; a market keeps the one its first bet
+    /// was paid in, or the one the owner set when it has no bets.
+    fn get_legacy_market_token(&self, market_id: u64) -> EgldOrEsdtTokenIdentifier {
+        let token_mapper = self.legacy_market_token(market_id);
+        let bet_ids = self.market_bet_ids(market_id);
+        if bet_ids.is_empty() {
+            require!(!token_mapper.is_empty(), "Legacy market token not set");
+            return token_mapper.take();
+        }
+
+        token_mapper.clear();
+        self.legacy_bet_by_id(bet_ids.get_by_index(1)).get().payment_token
+    }
+
+    /// Rebuilds one side of the order book from the first-version level list, at
+    /// the default odds scale. That list was kept with swap-remove, so levels are
+    /// re-sorted best price first and bets queued by id; totals come from the
+    /// bets themselves, since the stored ones drifted for Lay.
+    fn migrate_legacy_levels(&self, market_id: u64, selection_id: u64, side: BetType) {
+        let mut levels = match side {
+            BetType::Back => self.legacy_selection_back_levels(market_id, selection_id).get(),
+            BetType::Lay => self.legacy_selection_lay_levels(market_id, selection_id).get(),
+        };
+
+        let factor = BigUint::from(DEFAULT_ODDS_SCALE / LEGACY_ODDS_SCALE);
+        let mut liquidity = BigUint::zero();
+        while !levels.is_empty() {
+            let mut best = 0;
+            for index in 1..levels.len() {
+                if self.is_better_price(&side, &levels.get(index).odds, &levels.get(best).odds) {
+                    best = index;
+                }
+            }
+            let level = levels.get(best).clone();
+            levels.remove(best);
+
+            let odds = &level.odds * &factor;
+            liquidity += self.migrate_legacy_level(market_id, selection_id, side, &odds, &level);
+        }
+        self.selection_liquidity(market_id, selection_id, side).set(&liquidity);
+    }
+
+    fn migrate_legacy_level(
+        &self,
+        market_id: u64,
+        selection_id: u64,
+        side: BetType,
+        odds: &BigUint,
+        level: &LegacyPriceLevel<Self::Api>
+    ) -> BigUint {
+        let mut bet_ids = level.bet_nonces.clone();
+        let mut total_stake = BigUint::zero();
+        while !bet_ids.is_empty() {
+            let mut first = 0;
+            for index in 1..bet_ids.len() {
+                if bet_ids.get(index) < bet_ids.get(first) {
+                    first = index;
+                }
+            }
+            let bet_id = bet_ids.get(first);
+            bet_ids.remove(first);
+
+            let legacy_bet = self.legacy_bet_by_id(bet_id);
+            if legacy_bet.is_empty() {
+                continue;
+            }
+            let bet = legacy_bet.get();
+            let is_open = matches!(bet.status, BetStatus::Unmatched | BetStatus::PartiallyMatched);
+            if !is_open || bet.unmatched_amount == 0u64 {
+                continue;
+            }
+
+            if self.level_node_id(market_id, selection_id, side, odds).is_empty() {
+                let level_node = self.selection_level_odds(market_id, selection_id, side)
+                    .push_back(odds.clone());
+                self.level_node_id(market_id, selection_id, side, odds)
+                    .set(level_node.get_node_id());
+            }
+            let queue_node = self.level_queue(market_id, selection_id, side, odds)
+                .push_back(bet_id);
+            self.bet_queue_node(bet_id).set(queue_node.get_node_id());
+            self.user_open_bets(&bet.bettor).insert(bet_id);
+            total_stake += &bet.unmatched_amount;
+        }
+
+        if total_stake > 0u64 {
+            self.level_total_stake(market_id, selection_id, side, odds)
+                .update(|total| *total += &total_stake);
+        }
+        total_stake
+    }
+
+    /// Rewrites the bet at the default odds scale and rebuilds what the first
+    /// version did not keep: the bettor's indexes, the collateral reserved per
+    /// token and the exposure. Open bets missing from the book go back in, or
+    /// are refunded to the balance once their market has closed.
+    fn migrate_legacy_bet(&self, bet_id: u64) {
+        let legacy_mapper = self.legacy_bet_by_id(bet_id);
+        if legacy_mapper.is_empty() {
+            return;
+        }
+        let legacy = legacy_mapper.get();
+
+        let factor = BigUint::from(DEFAULT_ODDS_SCALE / LEGACY_ODDS_SCALE);
+        let mut matched_parts = ManagedVec::new();
+        for part in legacy.matched_parts.iter() {
+            matched_parts.push(MatchedPart { amount: part.amount.clone(), odds: &part.odds * &factor });
+        }
+        let mut bet = Bet {
+            id: bet_id,
+            bettor: legacy.bettor,
+            event: legacy.event,
+            selection: Selection { id: legacy.selection.id, value: legacy.selection.value },
+            stake_amount: legacy.stake_amount,
+            liability: legacy.liability,
+            matched_amount: legacy.matched_amount,
+            matched_parts,
+            unmatched_amount: legacy.unmatched_amount,
+            potential_profit: legacy.potential_profit,
+            odd: legacy.odd * &factor,
+            bet_type: legacy.bet_type,
+            status: legacy.status,
+            persistence: BetPersistence::Lapse,
+            payment_token: legacy.payment_token,
+            payment_nonce: legacy.payment_nonce,
+            nft_nonce: legacy.nft_nonce,
+            created_at: legacy.created_at,
+        };
+        self.legacy_locked_funds(&bet.bettor).clear();
+        self.legacy_user_total_exposure(&bet.bettor).clear();
+        self.user_bets(&bet.bettor).push(&bet_id);
+
+        if !bet.status.is_settled() && bet.bet_type == BetType::Lay {
+            self.fit_legacy_lay_collateral(&mut bet);
+        }
+        if bet.status.is_settled() {
+            self.bet_settled(bet_id).set(true);
+            self.user_settled_bets(&bet.bettor).push(&bet_id);
+            self.bet_by_id(bet_id).set(&bet);
+            return;
+        }
+
+        self.lock_funds(&bet.bettor, &bet.payment_token, &self.locked_collateral(&bet));
+        self.add_exposure(&bet);
+        self.index_user_bet(&bet);
+        self.bet_by_id(bet_id).set(&bet);
+
+        let is_open = bet.unmatched_amount > 0u64;
+        if !is_open || !self.bet_queue_node(bet_id).is_empty() {
+            return;
+        }
+        if self.markets(bet.event).get().market_status == MarketStatus::Open {
+            self.add_to_orderbook(&bet);
+        } else {
+            self.lapse_unmatched(bet_id);
+        }
+    }
+
+    /// The first version took only the stake from a layer, so the contract holds
+    /// that, not the liability. The unmatched part is cut to what the stake still
+    /// covers after the matched liability, and any stake left over goes to the
+    /// layer's balance. A matched liability above the stake cannot be made up
+    /// here: the bet keeps the stake as collateral and the gap shows in `getSolvency`.
+    fn fit_legacy_lay_collateral(&self, bet: &mut Bet<Self::Api>) {
+        let paid = bet.stake_amount.clone();
+        let matched_liability = self.calculate_matched_liability(bet);
+        let scale = BigUint::from(self.odds_scale().get());
+
+        let affordable = if paid > matched_liability {
+            (&paid - &matched_liability) * &scale / (&bet.odd - &scale)
+        } else {
+            BigUint::zero()
+        };
+        if affordable < bet.unmatched_amount {
+            if !self.bet_queue_node(bet.id).is_empty() {
+                if affordable == 0u64 {
+                    self.remove_from_orderbook(bet);
+                } else {
+                    self.reduce_in_orderbook(bet, &(&bet.unmatched_amount - &affordable));
+                }
+            }
+            bet.unmatched_amount = affordable;
+            bet.stake_amount = &bet.matched_amount + &bet.unmatched_amount;
+            if bet.unmatched_amount == 0u64 {
+                bet.status = if bet.matched_amount > 0u64 { BetStatus::Matched } else { BetStatus::Canceled };
+            }
+        }
+
+        let required = self.calculate_required_collateral(bet);
+        bet.liability = if required < paid { required } else { paid.clone() };
+        self.credit_balance(&bet.bettor, &bet.payment_token, &(&paid - &bet.liability));
+    }
+}
@@ -5,10 +5,6 @@ multiversx_sc::imports!();
 extern crate alloc;
 use alloc::string::ToString;
 
-const IPFS_GATEWAY: &[u8] = "https://ipfs.io/ipfs/".as_bytes();
-const METADATA_KEY_NAME: &[u8] = b"metadata:";
-const METADATA_FILE_EXTENSION: &[u8] = b".json";
-
 pub type AttributesAsMultiValue<M> =
     MultiValue7<u64, u64, BigUint<M>, BigUint<M>, BigUint<M>, BetType, BetStatus>;
 
@@ -50,24 +46,24 @@ pub trait NftModule:
         let big_one = BigUint::from(1u64);
 
         let mut token_name = ManagedBuffer::new_from_bytes(b"BetCube Ticket #");
-        let bet_id_buffer = self.u64_to_ascii(bet.nft_nonce);
+        let bet_id_buffer = self.u64_to_ascii(bet.id);
         token_name.append(&bet_id_buffer);
         let royalties = BigUint::from(NFT_ROYALTIES);
 
         self.metadata_cid().set(&metadata_cid);
         self.image_cid().set(&metadata_cid);
-        let metadata = self.build_metadata(bet.nft_nonce);
-        let uri = self.build_uri(bet.nft_nonce);
+        let metadata = self.build_metadata();
+        let uri = self.build_uri();
         let mut uris = ManagedVec::new();
         uris.push(uri);
 
         let attributes = BetAttributes {
-            event: bet.event.clone(),
+            event: bet.event,
             selection: bet.selection.clone(),
             stake: bet.stake_amount.clone(),
             potential_win: bet.potential_profit.clone(),
             odd: bet.odd.clone(),
-            bet_type: bet.bet_type.clone(),
+            bet_type: bet.bet_type,
             status: bet.status.clone(),
             metadata,
         };
@@ -85,7 +81,7 @@ pub trait NftModule:
             &big_one,
             &token_name,
             &royalties,
-            &attributes_hash,
+            attributes_hash,
             &attributes,
             &uris,
         );
@@ -101,7 +97,7 @@ pub trait NftModule:
         let payments = self.call_value().all_esdt_transfers().clone_value();
         let bet: Bet<<Self as ContractBase>::Api> = self.get_bet(bet_id);
 
-        if payments.len() == 0 {
+        if payments.is_empty() {
             require!(caller == bet.bettor, ERR_INVALID_ROLE);
         } else {
             require!(payments.len() == 1, "Invalid");
@@ -117,7 +113,7 @@ pub trait NftModule:
 
     fn u64_to_ascii(&self, number: u64) -> ManagedBuffer {
         let mut reversed_digits = ManagedVec::<Self::Api, u8>::new();
-        let mut result = number.clone();
+        let mut result = number;
 
         while result > 0 {
             let digit = result % 10;
@@ -140,7 +136,7 @@ pub trait NftModule:
             reversed_digits.push(digit_char);
         }
 
-        if &reversed_digits.len() == &0 {
+        if reversed_digits.is_empty() {
             return ManagedBuffer::new_from_bytes(b"0");
         }
 
@@ -153,17 +149,13 @@ pub trait NftModule:
         o
     }
 
-    fn build_uri(&self, number: u64) -> ManagedBuffer {
+    fn build_uri(&self) -> ManagedBuffer {
         let mut uri = ManagedBuffer::new_from_bytes(b"https://ipfs.io/ipfs/");
         
         let cid = self.image_cid().get();
         
-        let num_buffer = self.u64_to_ascii(number);
-        
         uri.append(&cid);
         uri.append_bytes(b"/");
-        // uri.append(&num_buffer);
-        // uri.append_bytes(b".png");
         
         uri
     }
@@ -174,21 +166,12 @@ pub trait NftModule:
         buf
     }
 
-    fn build_metadata(&self, number: u64) -> ManagedBuffer {
+    fn build_metadata(&self) -> ManagedBuffer {
         let cid = self.metadata_cid().get();
-        let num_buffer = self.u64_to_ascii(number);
-        
-        let total_len = 9 + // "metadata:"
-                       cid.len() +
-                       1 + // "/"
-                       num_buffer.len() +
-                       5; // ".json"
         
         let mut metadata = ManagedBuffer::new_from_bytes(b"metadata:");
         metadata.append(&cid);
         metadata.append_bytes(b"/");
-        // metadata.append(&num_buffer);
-        // metadata.append_bytes(b".json");
         
         metadata
     }
@@ -0,0 +1,410 @@
+use crate::constants::constants::{BASIS_POINTS, DEFAULT_MIN_ODDS, DEFAULT_ODDS_LADDER, DEFAULT_ODDS_SCALE, LEGACY_ODDS_SCALE};
+use crate::types::{BetType, OddsBand, OddsFormats, OddsInput, OddsMigrationProgress};
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+#[multiversx_sc::module]
+pub trait OddsModule:
+    crate::storage::StorageModule
+{
+    /// Replaces the odds ladder. Each band is `(up_to, tick)`, in increasing order
+    /// of `up_to`, and must end on a whole number of ticks. Prices are in units of
+    /// the current odds scale.
+    #[only_owner]
+    #[endpoint(setOddsLadder)]
+    fn set_odds_ladder(&self, min_odds: u64, bands: MultiValueEncoded<MultiValue2<u64, u64>>) {
+        require!(min_odds > self.odds_scale().get(), "Minimum odds must be above 1.00");
+        require!(!bands.is_empty(), "Odds ladder cannot be empty");
+
+        let mut ladder = ManagedVec::new();
+        let mut lower = min_odds;
+        for band in bands.into_iter() {
+            let (up_to, tick) = band.into_tuple();
+            require!(tick > 0, "Invalid odds tick");
+            require!(up_to > lower, "Odds bands must be increasing");
+            require!((up_to - lower).is_multiple_of(tick), "Odds band must end on a tick");
+
+            ladder.push(OddsBand { up_to, tick });
+            lower = up_to;
+        }
+
+        self.odds_ladder_min().set(min_odds);
+        self.odds_ladder().set(&ladder);
+    }
+
+    fn init_odds(&self) {
+        self.odds_scale().set(DEFAULT_ODDS_SCALE);
+        self.init_default_odds_ladder();
+    }
+
+    /// Contracts deployed before the scale was configurable keep odds in
+    /// hundredths; `migrateLegacyState` moves them to the default scale.
+    fn upgrade_odds(&self) {
+        if self.odds_scale().is_empty() {
+            self.odds_scale().set(DEFAULT_ODDS_SCALE);
+            self.init_default_odds_ladder();
+        }
+    }
+
+    fn init_default_odds_ladder(&self) {
+        if !self.odds_ladder().is_empty() {
+            return;
+        }
+
+        // Scara implicită e scrisă în sutimi
+        let factor = self.odds_scale().get() / LEGACY_ODDS_SCALE;
+        let mut ladder = ManagedVec::new();
+        for (up_to, tick) in DEFAULT_ODDS_LADDER {
+            ladder.push(OddsBand { up_to: up_to * factor, tick: tick * factor });
+        }
+        self.odds_ladder_min().set(DEFAULT_MIN_ODDS * factor);
+        self.odds_ladder().set(&ladder);
+    }
+
+    //--------------------------------------------------------------------------------------------//
+    //-------------------------------- Scale Migration -------------------------------------------//
+    //--------------------------------------------------------------------------------------------//
+
+    /// Moves every stored price to a finer scale. The new scale must be a multiple
+    /// of the current one so no price is rounded. Betting, cancelling and settlement
+    /// are paused until `migrateOdds` has gone through all bets and markets.
+    #[only_owner]
+    #[endpoint(setOddsScale)]
+    fn set_odds_scale(&self, new_scale: u64) {
+        self.require_odds_not_migrating();
+        self.start_odds_migration(new_scale);
+    }
+
+    fn start_odds_migration(&self, new_scale: u64) {
+        let current_scale = self.odds_scale().get();
+        require!(
+            new_scale > current_scale && new_scale.is_multiple_of(current_scale),
+            "New scale must be a multiple of the current scale"
+        );
+
+        self.odds_migration_scale().set(new_scale);
+        self.odds_migration_bet_cursor().set(1);
+        self.odds_migration_market_cursor().set(1);
+    }
+
+    /// Rescales up to `batch_size` bets, then the order books of up to `batch_size`
+    /// markets. Can be called again until it returns true.
+    #[only_owner]
+    #[endpoint(migrateOdds)]
+    fn migrate_odds(&self, batch_size: u64) -> bool {
+        require!(!self.odds_migration_scale().is_empty(), "No odds migration in progress");
+        require!(batch_size > 0, "Invalid batch size");
+
+        let new_scale = self.odds_migration_scale().get();
+        let factor = BigUint::from(new_scale / self.odds_scale().get());
+
+        let last_bet_id = self.last_bet_id().get();
+        let mut bet_cursor = self.odds_migration_bet_cursor().get();
+        let mut processed = 0u64;
+        while bet_cursor <= last_bet_id && processed < batch_size {
+            self.rescale_bet_odds(bet_cursor, &factor);
+            bet_cursor += 1;
+            processed += 1;
+        }
+        self.odds_migration_bet_cursor().set(bet_cursor);
+
+        let last_market_id = self.market_counter().get();
+        let mut market_cursor = self.odds_migration_market_cursor().get();
+        while market_cursor <= last_market_id && processed < batch_size {
+            self.rescale_market_levels(market_cursor, &factor);
+            market_cursor += 1;
+            processed += 1;
+        }
+        self.odds_migration_market_cursor().set(market_cursor);
+
+        if bet_cursor <= last_bet_id || market_cursor <= last_market_id {
+            return false;
+        }
+
+        let factor = factor.to_u64().unwrap_or_default();
+        let mut ladder = ManagedVec::new();
+        for band in self.odds_ladder().get().iter() {
+            ladder.push(OddsBand { up_to: band.up_to * factor, tick: band.tick * factor });
+        }
+        self.odds_ladder().set(&ladder);
+        self.odds_ladder_min().update(|min| *min *= factor);
+
+        self.odds_scale().set(new_scale);
+        self.odds_migration_scale().clear();
+        self.odds_migration_bet_cursor().clear();
+        self.odds_migration_market_cursor().clear();
+        true
+    }
+
+    fn rescale_bet_odds(&self, bet_id: u64, factor: &BigUint) {
+        let bet_mapper = self.bet_by_id(bet_id);
+        if bet_mapper.is_empty() {
+            return;
+        }
+
+        let mut bet = bet_mapper.get();
+        bet.odd *= factor;
+        let mut matched_parts = ManagedVec::new();
+        for mut part in bet.matched_parts.iter() {
+            part.odds *= factor;
+            matched_parts.push(part);
+        }
+        bet.matched_parts = matched_parts;
+        bet_mapper.set(&bet);
+    }
+
+    /// Re-keys every price level of the market under its rescaled odds, keeping
+    /// the order of the levels and of the bets queued in each of them.
+    fn rescale_market_levels(&self, market_id: u64, factor: &BigUint) {
+        let market_mapper = self.markets(market_id);
+        if market_mapper.is_empty() {
+            return;
+        }
+
+        for selection in market_mapper.get().selections.iter() {
+            for side in [BetType::Back, BetType::Lay] {
+                let mut odds_index = self.selection_level_odds(market_id, selection.id, side);
+                let mut levels = ManagedVec::<Self::Api, BigUint>::new();
+                for level_node in odds_index.iter() {
+                    levels.push(level_node.into_value());
+                }
+                if levels.is_empty() {
+                    continue;
+                }
+
+                // Cheia nouă a unui nivel poate fi cheia veche a unui nivel cu cotă mai mare,
+                // așa că mutăm nivelurile de la cota cea mai mare în jos
+                let ascending = levels.get(0).clone_value() < levels.get(levels.len() - 1).clone_value();
+                for position in 0..levels.len() {
+                    let index = if ascending { levels.len() - 1 - position } else { position };
+                    let old_odds = levels.get(index).clone_value();
+                    let new_odds = &old_odds * factor;
+                    self.rekey_level(market_id, selection.id, side, &old_odds, &new_odds);
+
+                    let level_node_id = self.level_node_id(market_id, selection.id, side, &new_odds).get();
+                    odds_index.set_node_value_by_id(level_node_id, new_odds);
+                }
+            }
+        }
+    }
+
+    fn rekey_level(
+        &self,
+        market_id: u64,
+        selection_id: u64,
+        side: BetType,
+        old_odds: &BigUint,
+        new_odds: &BigUint
+    ) {
+        let mut old_queue = self.level_queue(market_id, selection_id, side, old_odds);
+        let mut new_queue = self.level_queue(market_id, selection_id, side, new_odds);
+        for queue_node in old_queue.iter() {
+            let bet_id = queue_node.into_value();
+            let new_node = new_queue.push_back(bet_id);
+            self.bet_queue_node(bet_id).set(new_node.get_node_id());
+        }
+        old_queue.clear();
+
+        let old_total = self.level_total_stake(market_id, selection_id, side, old_odds);
+        self.level_total_stake(market_id, selection_id, side, new_odds).set(old_total.get());
+        old_total.clear();
+
+        let old_node_id = self.level_node_id(market_id, selection_id, side, old_odds);
+        self.level_node_id(market_id, selection_id, side, new_odds).set(old_node_id.get());
+        old_node_id.clear();
+    }
+
+    fn require_odds_not_migrating(&self) {
+        require!(self.odds_migration_scale().is_empty(), "Odds migration in progress");
+        require!(self.legacy_migration_market_cursor().is_empty(), "Legacy migration in progress");
+    }
+
+    #[view(getOddsMigrationProgress)]
+    fn get_odds_migration_progress(&self) -> OddsMigrationProgress {
+        let total_bets = self.last_bet_id().get();
+        let total_markets = self.market_counter().get();
+        if self.odds_migration_scale().is_empty() {
+            return OddsMigrationProgress {
+                current_scale: self.odds_scale().get(),
+                target_scale: self.odds_scale().get(),
+                bets_migrated: total_bets,
+                total_bets,
+                markets_migrated: total_markets,
+                total_markets,
+            };
+        }
+
+        OddsMigrationProgress {
+            current_scale: self.odds_scale().get(),
+            target_scale: self.odds_migration_scale().get(),
+            bets_migrated: self.odds_migration_bet_cursor().get() - 1,
+            total_bets,
+            markets_migrated: self.odds_migration_market_cursor().get() - 1,
+            total_markets,
+        }
+    }
+
+    #[view(getOddsScale)]
+    fn get_odds_scale(&self) -> u64 {
+        self.odds_scale().get()
+    }
+
+    //--------------------------------------------------------------------------------------------//
+    //-------------------------------- Ladder ----------------------------------------------------//
+    //--------------------------------------------------------------------------------------------//
+
+    fn is_on_odds_ladder(&self, odds: &BigUint) -> bool {
+        let odds = match odds.to_u64() {
+            Some(odds) => odds,
+            None => return false,
+        };
+
+        let mut lower = self.odds_ladder_min().get();
+        if odds == lower {
+            return true;
+        }
+        for band in self.odds_ladder().get().iter() {
+            if odds > lower && odds <= band.up_to {
+                return (odds - lower).is_multiple_of(band.tick);
+            }
+            lower = band.up_to;
+        }
+        false
+    }
+
+    /// Smallest ladder price strictly above `odds`.
+    #[view(getNextTickUp)]
+    fn get_next_tick_up(&self, odds: u64) -> u64 {
+        let mut lower = self.odds_ladder_min().get();
+        if odds < lower {
+            return lower;
+        }
+        for band in self.odds_ladder().get().iter() {
+            if odds < band.up_to {
+                return lower + ((odds - lower) / band.tick + 1) * band.tick;
+            }
+            lower = band.up_to;
+        }
+        sc_panic!("No tick above maximum odds")
+    }
+
+    /// Largest ladder price strictly below `odds`.
+    #[view(getNextTickDown)]
+    fn get_next_tick_down(&self, odds: u64) -> u64 {
+        let mut lower = self.odds_ladder_min().get();
+        require!(odds > lower, "No tick below minimum odds");
+        for band in self.odds_ladder().get().iter() {
+            if odds <= band.up_to {
+                return lower + ((odds - lower - 1) / band.tick) * band.tick;
+            }
+            lower = band.up_to;
+        }
+        lower
+    }
+
+    #[view(getOddsLadder)]
+    fn get_odds_ladder(&self) -> MultiValue2<u64, ManagedVec<OddsBand>> {
+        (self.odds_ladder_min().get(), self.odds_ladder().get()).into()
+    }
+
+    //--------------------------------------------------------------------------------------------//
+    //-------------------------------- Formats ---------------------------------------------------//
+    //--------------------------------------------------------------------------------------------//
+
+    /// Fractional odds are reduced; American odds and implied probability are
+    /// truncated to whole units.
+    #[view(getOddsFormats)]
+    fn get_odds_formats(&self, odds: u64) -> OddsFormats {
+        let scale = self.odds_scale().get();
+        require!(odds > scale, "Odds must be above 1.00");
+
+        let profit = odds - scale;
+        let divisor = self.greatest_common_divisor(profit, scale);
+        let moneyline = if profit >= scale {
+            self.checked_odds_product(profit, Some(100)) / scale
+        } else {
+            self.checked_odds_product(scale, Some(100)) / profit
+        };
+        let moneyline = i64::try_from(moneyline).unwrap_or_else(|_| sc_panic!("Odds out of range"));
+        let american = if profit >= scale { moneyline } else { -moneyline };
+
+        OddsFormats {
+            decimal: odds,
+            fractional_numerator: profit / divisor,
+            fractional_denominator: scale / divisor,
+            american,
+            implied_probability_bps: self.checked_odds_product(scale, Some(BASIS_POINTS)) / odds,
+        }
+    }
+
+    /// Converts odds quoted in any format onto the ladder. A Back is rounded up
+    /// and a Lay down, so neither is placed at a worse price than asked for.
+    #[view(normalizeOdds)]
+    fn normalize_odds(&self, odds: OddsInput, bet_type: BetType) -> u64 {
+        let scale = self.odds_scale().get();
+        // Cota ca fracție numerator / denominator, în unități ale scării
+        let (numerator, denominator) = match odds {
+            OddsInput::Decimal(value) => (value, 1),
+            OddsInput::Fractional(profit, stake) => {
+                require!(stake > 0, "Invalid fractional odds");
+                (self.checked_odds_product(scale, profit.checked_add(stake)), stake)
+            },
+            OddsInput::American(moneyline) => {
+                let magnitude = moneyline.unsigned_abs();
+                require!(magnitude >= 100, "Invalid American odds");
+                let payout = self.checked_odds_product(scale, magnitude.checked_add(100));
+                if moneyline > 0 {
+                    (payout, 100)
+                } else {
+                    (payout, magnitude)
+                }
+            },
+            OddsInput::ImpliedProbability(probability_bps) => {
+                require!(
+                    probability_bps > 0 && probability_bps < BASIS_POINTS,
+                    "Invalid implied probability"
+                );
+                (self.checked_odds_product(scale, Some(BASIS_POINTS)), probability_bps)
+            },
+        };
+
+        let floor = numerator / denominator;
+        let exact = floor * denominator == numerator;
+        match bet_type {
+            BetType::Back => {
+                let ceil = if exact { floor } else { floor + 1 };
+                if self.is_on_odds_ladder(&BigUint::from(ceil)) {
+                    ceil
+                } else {
+                    self.get_next_tick_up(ceil)
+                }
+            },
+            BetType::Lay => {
+                if self.is_on_odds_ladder(&BigUint::from(floor)) {
+                    floor
+                } else {
+                    self.get_next_tick_down(floor)
+                }
+            },
+        }
+    }
+
+    /// `scale * factor` for a price given by the caller; a factor that already
+    /// overflowed is `None`. Wasm has no overflow checks, so a wrapped price
+    /// could otherwise land on the ladder.
+    fn checked_odds_product(&self, scale: u64, factor: Option<u64>) -> u64 {
+        factor
+            .and_then(|factor| scale.checked_mul(factor))
+            .unwrap_or_else(|| sc_panic!("Odds out of range"))
+    }
+
+    fn greatest_common_divisor(&self, a: u64, b: u64) -> u64 {
+        let (mut a, mut b) = (a, b);
+        while b != 0 {
+            let remainder = a % b;
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+}
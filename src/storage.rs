@@ -1,4 +1,4 @@
-use crate::types::{Bet, Market, PriceLevel, Tracker};
+use crate::types::{Bet, BetType, Event, ExposurePosition, LegacyBet, LegacyMarket, LegacyPriceLevel, LegacyTracker, Market, MarketType, OddsBand, SelfTradePrevention, SettlementCounters, StakeLimits, WinningSelection};
 
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
@@ -11,6 +11,22 @@ pub trait StorageModule {
     #[storage_mapper("marketBetIds")]
     fn market_bet_ids(&self, market_id: u64) -> UnorderedSetMapper<u64>;
 
+    // Istoricul complet al pariurilor unui user, în ordinea plasării
+    #[storage_mapper("userBets")]
+    fn user_bets(&self, bettor: &ManagedAddress) -> VecMapper<u64>;
+
+    // Pariurile unui user care au încă o parte în orderbook
+    #[storage_mapper("userOpenBets")]
+    fn user_open_bets(&self, bettor: &ManagedAddress) -> UnorderedSetMapper<u64>;
+
+    // Pariurile complet matched ale unui user, încă nedecontate
+    #[storage_mapper("userMatchedBets")]
+    fn user_matched_bets(&self, bettor: &ManagedAddress) -> UnorderedSetMapper<u64>;
+
+    // Pariurile decontate sau anulate ale unui user, în ordinea decontării
+    #[storage_mapper("userSettledBets")]
+    fn user_settled_bets(&self, bettor: &ManagedAddress) -> VecMapper<u64>;
+
     #[storage_mapper("betNftToken")]
     fn bet_nft_token(&self) -> NonFungibleTokenMapper<Self::Api>;
 
@@ -20,13 +36,70 @@ pub trait StorageModule {
     #[storage_mapper("market_counter")]
     fn market_counter(&self) -> SingleValueMapper<u64>;
 
-    #[storage_mapper("selection_back_levels")]
-    fn selection_back_levels(&self, market_id: u64, selection_id: u64)
-        -> SingleValueMapper<ManagedVec<Self::Api, PriceLevel<Self::Api>>>;
+    #[storage_mapper("last_bet_id")]
+    fn last_bet_id(&self) -> SingleValueMapper<u64>;
 
-    #[storage_mapper("selection_lay_levels")]
-    fn selection_lay_levels(&self, market_id: u64, selection_id: u64)
-        -> SingleValueMapper<ManagedVec<Self::Api, PriceLevel<Self::Api>>>;
+    // Câte unități înseamnă cota 1.00
+    #[storage_mapper("odds_scale")]
+    fn odds_scale(&self) -> SingleValueMapper<u64>;
+
+    // Scara către care migrăm cotele deja stocate; goală când nu e nicio migrare
+    #[storage_mapper("odds_migration_scale")]
+    fn odds_migration_scale(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("odds_migration_bet_cursor")]
+    fn odds_migration_bet_cursor(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("odds_migration_market_cursor")]
+    fn odds_migration_market_cursor(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("odds_ladder_min")]
+    fn odds_ladder_min(&self) -> SingleValueMapper<u64>;
+
+    // Benzile scării de cote, crescător după up_to
+    #[storage_mapper("odds_ladder")]
+    fn odds_ladder(&self) -> SingleValueMapper<ManagedVec<OddsBand>>;
+
+    // Adresa care, pe lângă owner, poate anula piețe
+    #[storage_mapper("oracle")]
+    fn oracle(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[storage_mapper("voidReason")]
+    fn void_reason(&self, market_id: u64) -> SingleValueMapper<ManagedBuffer>;
+
+    // Tokenii în care se pot crea piețe
+    #[storage_mapper("accepted_tokens")]
+    fn accepted_tokens(&self) -> UnorderedSetMapper<EgldOrEsdtTokenIdentifier>;
+
+    #[storage_mapper("token_stake_limits")]
+    fn token_stake_limits(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<StakeLimits<Self::Api>>;
+
+    #[storage_mapper("market_stake_limits")]
+    fn market_stake_limits(&self, market_id: u64, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<StakeLimits<Self::Api>>;
+
+    #[storage_mapper("self_trade_prevention")]
+    fn self_trade_prevention(&self) -> SingleValueMapper<SelfTradePrevention>;
+
+    // Odds of the non-empty price levels on one side, best price first
+    #[storage_mapper("selection_level_odds")]
+    fn selection_level_odds(&self, market_id: u64, selection_id: u64, side: BetType)
+        -> LinkedListMapper<BigUint<Self::Api>>;
+
+    #[storage_mapper("level_node_id")]
+    fn level_node_id(&self, market_id: u64, selection_id: u64, side: BetType, odds: &BigUint)
+        -> SingleValueMapper<u32>;
+
+    // FIFO queue of the bet nonces resting at one price
+    #[storage_mapper("level_queue")]
+    fn level_queue(&self, market_id: u64, selection_id: u64, side: BetType, odds: &BigUint)
+        -> LinkedListMapper<u64>;
+
+    #[storage_mapper("level_total_stake")]
+    fn level_total_stake(&self, market_id: u64, selection_id: u64, side: BetType, odds: &BigUint)
+        -> SingleValueMapper<BigUint<Self::Api>>;
+
+    #[storage_mapper("bet_queue_node")]
+    fn bet_queue_node(&self, bet_id: u64) -> SingleValueMapper<u32>;
 
     #[storage_mapper("selection_back_liquidity")]
     fn selection_back_liquidity(&self, market_id: u64, selection_id: u64)
@@ -60,10 +133,6 @@ pub trait StorageModule {
     fn selection_canceled_count(&self, market_id: u64, selection_id: u64)
         -> SingleValueMapper<u64>;
 
-    #[storage_mapper("selection_tracker")]
-    fn selection_tracker(&self, market_id: u64, selection_id: u64)
-        -> SingleValueMapper<Tracker<Self::Api>>;
-
     #[storage_mapper("markets")]
     fn markets(&self, market_id: u64) -> SingleValueMapper<Market<Self::Api>>;
 
@@ -71,26 +140,191 @@ pub trait StorageModule {
     fn total_matched_amount(&self, market_id: u64, selection_id: u64)
         -> SingleValueMapper<BigUint<Self::Api>>;
 
+    #[storage_mapper("market_total_matched")]
+    fn market_total_matched(&self, market_id: u64) -> SingleValueMapper<BigUint<Self::Api>>;
+
+    // Partea rezervată din sold: colateralul pariurilor încă nedecontate
     #[storage_mapper("locked_funds")]
-    fn locked_funds(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint<Self::Api>>;
+    fn locked_funds(
+        &self,
+        address: &ManagedAddress,
+        token: &EgldOrEsdtTokenIdentifier
+    ) -> SingleValueMapper<BigUint<Self::Api>>;
+
+    // Totalurile pe token ale obligațiilor contractului față de useri
+    #[storage_mapper("total_locked")]
+    fn total_locked(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint<Self::Api>>;
+
+    #[storage_mapper("total_user_balances")]
+    fn total_user_balances(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint<Self::Api>>;
+
+    #[storage_mapper("total_unclaimed")]
+    fn total_unclaimed(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint<Self::Api>>;
+
+    // Soldul disponibil al userului, din depuneri, refund-uri și câștiguri
+    #[storage_mapper("user_balance")]
+    fn user_balance(
+        &self,
+        address: &ManagedAddress,
+        token: &EgldOrEsdtTokenIdentifier
+    ) -> SingleValueMapper<BigUint<Self::Api>>;
     
+    // Expunerea unui user pe toate piețele în același token
     #[storage_mapper("user_exposure")]
     fn user_total_exposure(
         &self,
-        user: &ManagedAddress<Self::Api>
+        user: &ManagedAddress<Self::Api>,
+        token: &EgldOrEsdtTokenIdentifier
+    ) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("user_market_exposure")]
+    fn user_market_exposure(&self, user: &ManagedAddress, market_id: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("user_position")]
+    fn user_position(
+        &self,
+        user: &ManagedAddress,
+        market_id: u64,
+        selection_id: u64
+    ) -> SingleValueMapper<ExposurePosition<Self::Api>>;
+
+    // Profitul unui Back sau liability-ul unui Lay, așa cum a intrat în poziție
+    #[storage_mapper("bet_at_risk")]
+    fn bet_at_risk(&self, bet_id: u64) -> SingleValueMapper<BigUint>;
+
+    // Suma expunerilor tuturor userilor pe toate piețele unui token
+    #[storage_mapper("total_exposure")]
+    fn total_exposure(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    // Plafonul implicit pentru expunerea totală a unui user într-un token; gol înseamnă fără plafon
+    #[storage_mapper("max_user_exposure")]
+    fn max_user_exposure(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("user_exposure_cap")]
+    fn user_exposure_cap(
+        &self,
+        user: &ManagedAddress,
+        token: &EgldOrEsdtTokenIdentifier
     ) -> SingleValueMapper<BigUint>;
 
+    // Plafonul expunerii unui user pe o singură piață
+    #[storage_mapper("market_exposure_cap")]
+    fn market_exposure_cap(&self, market_id: u64) -> SingleValueMapper<BigUint>;
+
     #[storage_mapper("marketsByEvent")]
     fn markets_by_event(&self, event_id: u64) -> SingleValueMapper<ManagedVec<u64>>;
 
-    #[storage_mapper("winningSelection")]
-    fn winning_selection(&self, market_id: u64) -> SingleValueMapper<u64>;
+    // Selecțiile câștigătoare, fiecare cu fracțiunea de dead-heat
+    #[storage_mapper("winningSelections")]
+    fn winning_selections(&self, market_id: u64) -> SingleValueMapper<ManagedVec<WinningSelection>>;
 
     #[storage_mapper("currentProcessingIndex")]
     fn current_processing_index(&self, market_id: u64) -> SingleValueMapper<u64>;
 
+    // Suma de plată calculată la decontare, ridicată prin claim
+    #[storage_mapper("betPayout")]
+    fn bet_payout(&self, bet_id: u64) -> SingleValueMapper<BigUint>;
+
+    // Rezultatul net al userului pe piață, adunat la decontare pentru comision
+    #[storage_mapper("userMarketResult")]
+    fn user_market_result(&self, user: &ManagedAddress, market_id: u64) -> SingleValueMapper<BigInt>;
+
+    #[storage_mapper("userMarketWinningBets")]
+    fn user_market_winning_bets(&self, user: &ManagedAddress, market_id: u64) -> VecMapper<u64>;
+
+    #[storage_mapper("marketBettors")]
+    fn market_bettors(&self, market_id: u64) -> UnorderedSetMapper<ManagedAddress>;
+
+    #[storage_mapper("feeProcessingIndex")]
+    fn fee_processing_index(&self, market_id: u64) -> SingleValueMapper<u64>;
+
+    // Comisionul în puncte de bază
+    #[storage_mapper("commissionRate")]
+    fn commission_rate(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("marketCommissionRate")]
+    fn market_commission_rate(&self, market_id: u64) -> SingleValueMapper<u64>;
+
+    // Reducerea din comision a fiecărui nivel, în puncte de bază
+    #[storage_mapper("discountTier")]
+    fn discount_tier(&self, tier: u8) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("userDiscountTier")]
+    fn user_discount_tier(&self, user: &ManagedAddress) -> SingleValueMapper<u8>;
+
+    #[storage_mapper("feeTreasury")]
+    fn fee_treasury(&self, token: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("settlementCounters")]
+    fn settlement_counters(&self, market_id: u64) -> SingleValueMapper<SettlementCounters>;
+
+    #[storage_mapper("betSettled")]
+    fn bet_settled(&self, bet_id: u64) -> SingleValueMapper<bool>;
+
+    #[storage_mapper("betClaimed")]
+    fn bet_claimed(&self, bet_id: u64) -> SingleValueMapper<bool>;
+
+    #[storage_mapper("marketTypes")]
+    fn market_types(&self, market_type_id: u64) -> SingleValueMapper<MarketType<Self::Api>>;
+
+    #[storage_mapper("marketTypeCounter")]
+    fn market_type_counter(&self) -> SingleValueMapper<u64>;
+
+    // Tipul din catalog al unei piețe; 0 pentru piețele create cu selecții libere
+    #[storage_mapper("marketTypeOf")]
+    fn market_type_of(&self, market_id: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("events")]
+    fn events(&self, event_id: u64) -> SingleValueMapper<Event<Self::Api>>;
+
+    #[storage_mapper("eventCounter")]
+    fn event_counter(&self) -> SingleValueMapper<u64>;
+
+    // Evenimentele după ora de start, citite de processEventMarkets
     #[storage_mapper("eventsByTimestamp")]
     fn events_by_timestamp(&self, timestamp: u64) -> SingleValueMapper<ManagedVec<u64>>;
 
-}
+    #[storage_mapper("eventsByCompetition")]
+    fn events_by_competition(&self, competition: &ManagedBuffer) -> SingleValueMapper<ManagedVec<u64>>;
 
+    // Aceleași chei citite cu layout-ul din prima versiune, până le mută migrateLegacyState
+    #[storage_mapper("markets")]
+    fn legacy_markets(&self, market_id: u64) -> SingleValueMapper<LegacyMarket<Self::Api>>;
+
+    #[storage_mapper("betById")]
+    fn legacy_bet_by_id(&self, bet_id: u64) -> SingleValueMapper<LegacyBet<Self::Api>>;
+
+    #[storage_mapper("selection_back_levels")]
+    fn legacy_selection_back_levels(&self, market_id: u64, selection_id: u64)
+        -> SingleValueMapper<ManagedVec<Self::Api, LegacyPriceLevel<Self::Api>>>;
+
+    #[storage_mapper("selection_lay_levels")]
+    fn legacy_selection_lay_levels(&self, market_id: u64, selection_id: u64)
+        -> SingleValueMapper<ManagedVec<Self::Api, LegacyPriceLevel<Self::Api>>>;
+
+    #[storage_mapper("selection_tracker")]
+    fn legacy_selection_tracker(&self, market_id: u64, selection_id: u64)
+        -> SingleValueMapper<LegacyTracker<Self::Api>>;
+
+    // Singura selecție câștigătoare, cum o ținea prima versiune
+    #[storage_mapper("winningSelection")]
+    fn legacy_winning_selection(&self, market_id: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("locked_funds")]
+    fn legacy_locked_funds(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint<Self::Api>>;
+
+    #[storage_mapper("user_exposure")]
+    fn legacy_user_total_exposure(&self, user: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    // Următoarea piață de migrat; goală când nu e nicio migrare
+    #[storage_mapper("legacy_migration_market_cursor")]
+    fn legacy_migration_market_cursor(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("legacy_migration_bet_cursor")]
+    fn legacy_migration_bet_cursor(&self) -> SingleValueMapper<u64>;
+
+    // Tokenul dat de owner pentru o piață veche fără pariuri
+    #[storage_mapper("legacy_market_token")]
+    fn legacy_market_token(&self, market_id: u64) -> SingleValueMapper<EgldOrEsdtTokenIdentifier>;
+
+}
@@ -1,105 +1,146 @@
-use crate::types::{Bet, BetMatchingState, BetStatus, BetType, BetView, MatchedPart, MatchingDetails, OrderbookView, PriceLevel, PriceLevelView};
+use crate::constants::constants::MAX_LEVEL_DEPTH;
+use crate::types::{Bet, BetMatchingState, BetStatus, BetType, BetView, FormattedLadder, FormattedPriceLevel, MatchedPart, MatchingDetails, PriceLevelView, QueuePosition, SelfTradePrevention, SettlementCounters};
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
 #[multiversx_sc::module]
 pub trait TrackerModule:
     crate::storage::StorageModule +
-    crate::events::EventsModule
+    crate::events::EventsModule +
+    crate::validation::ValidationModule +
+    crate::odds::OddsModule +
+    crate::exposure::ExposureModule +
+    crate::ledger::LedgerModule
 {
 
-    fn process_bet(&self, mut bet: Bet<Self::Api>) -> (BigUint, BigUint) {
+    /// Matches the bet against the opposite side of the book. With `rest_unmatched`
+    /// off the remainder is left out of the book, for the caller to release.
+    fn process_bet(&self, mut bet: Bet<Self::Api>, rest_unmatched: bool) -> Bet<Self::Api> {
         let mut matched_amount = bet.matched_amount.clone();
         let mut remaining = bet.unmatched_amount.clone();
+        let mut new_matches = BigUint::zero();
+
+        let market_id = bet.event;
+        let selection_id = bet.selection.id;
+        let opposite_side = bet.bet_type.opposite();
+        let opposite_odds = self.selection_level_odds(market_id, selection_id, opposite_side);
+        let self_trade_prevention = self.self_trade_prevention().get();
+        let mut cancel_incoming = false;
     
-        let mut opposite_levels = match bet.bet_type {
-            BetType::Back => self.selection_lay_levels(bet.event, bet.selection.id).get(),
-            BetType::Lay => self.selection_back_levels(bet.event, bet.selection.id).get(),
-        };
-    
-        let mut i = 0;
-        while i < opposite_levels.len() && remaining > BigUint::zero() {
-            let mut level = opposite_levels.get(i);
-            
-            if level.odds == bet.odd {
-                let match_amount = match bet.bet_type {
-                    BetType::Back => {
-                        remaining.clone().min(level.total_stake.clone())
-                    },
-                    BetType::Lay => {
-                        bet.liability.clone().min(level.total_stake.clone())
-                    }
+        // Nivelurile sunt ordonate de la cel mai bun preț; cu Skip un nivel poate rămâne
+        // doar cu pariurile proprii, așa că trecem la următorul
+        let mut next_level = opposite_odds.front();
+        while remaining > BigUint::zero() && !cancel_incoming {
+            let level_node = match next_level {
+                Some(node) => node,
+                None => break,
+            };
+            next_level = opposite_odds.get_node_by_id(level_node.get_next_node_id());
+            let level_odds = level_node.into_value();
+            if !self.crosses_level(&bet, &level_odds) {
+                break;
+            }
+
+            // Împărțim matching-ul între pariurile din nivel, în ordinea sosirii (FIFO)
+            let mut queue = self.level_queue(market_id, selection_id, opposite_side, &level_odds);
+            let mut level_matched = BigUint::zero();
+            let mut next_queue_node = queue.front();
+            while remaining > BigUint::zero() {
+                let queue_node = match next_queue_node {
+                    Some(node) => node,
+                    None => break,
                 };
-                
-                if match_amount > BigUint::zero() {
-                    // Adăugăm noua parte matched cu cota curentă
-                    bet.matched_parts.push(MatchedPart {
-                        amount: match_amount.clone(),
-                        odds: level.odds.clone()
-                    });
-
-                    matched_amount += &match_amount;
-                    remaining -= &match_amount;
-                    level.total_stake -= &match_amount;
-    
-                    // Update matched bets
-                    let mut updated_nonces = ManagedVec::new();
-                    for nonce in level.bet_nonces.iter() {
-                        let mut matched_bet = self.bet_by_id(nonce).get();
-                        if matched_bet.unmatched_amount > BigUint::zero() {
-                            let match_this_bet = matched_bet.unmatched_amount.clone().min(match_amount.clone());
-                            
-                            if match_this_bet > BigUint::zero() {
-                                // Adăugăm partea matched și pentru pariul opus
-                                matched_bet.matched_parts.push(MatchedPart {
-                                    amount: match_this_bet.clone(),
-                                    odds: matched_bet.odd.clone()
-                                });
-
-                                matched_bet.matched_amount += &match_this_bet;
-                                matched_bet.unmatched_amount -= &match_this_bet;
-                                
-                                matched_bet.status = if matched_bet.unmatched_amount == BigUint::zero() {
-                                    BetStatus::Matched
-                                } else {
-                                    BetStatus::PartiallyMatched
-                                };
-                                
-                                // Recalculăm potential profit pentru pariul opus
-                                matched_bet.potential_profit = self.calculate_total_potential_profit(&matched_bet);
-                                
-                                self.bet_by_id(nonce).set(&matched_bet);
-    
-                                if matched_bet.unmatched_amount > BigUint::zero() {
-                                    updated_nonces.push(nonce);
-                                }
-                            }
-                        }
+                next_queue_node = queue.get_node_by_id(queue_node.get_next_node_id());
+                let nonce = queue_node.get_value_cloned();
+                let mut matched_bet = self.bet_by_id(nonce).get();
+                // O piață are un singur token, deci orice pariu din orderbook e plătit în el
+                require!(matched_bet.payment_token == bet.payment_token, "Invalid payment token");
+
+                if matched_bet.bettor == bet.bettor {
+                    self.self_trade_prevented_event(
+                        &bet.bettor,
+                        market_id,
+                        selection_id,
+                        bet.id,
+                        nonce,
+                        self_trade_prevention
+                    );
+                    match self_trade_prevention {
+                        SelfTradePrevention::CancelResting => {
+                            self.level_total_stake(market_id, selection_id, opposite_side, &level_odds)
+                                .update(|total| *total -= &level_matched);
+                            level_matched = BigUint::zero();
+                            self.lapse_unmatched(nonce);
+                            continue;
+                        },
+                        SelfTradePrevention::CancelIncoming => {
+                            cancel_incoming = true;
+                            break;
+                        },
+                        SelfTradePrevention::Skip => continue,
                     }
-    
-                    if !updated_nonces.is_empty() {
-                        level.bet_nonces = updated_nonces;
-                        let _ = opposite_levels.set(i, level);
-                        i += 1;
-                    } else {
-                        if i < opposite_levels.len() - 1 {
-                            let last = opposite_levels.get(opposite_levels.len() - 1);
-                            let _ = opposite_levels.set(i, last);
-                        }
-                        opposite_levels.remove(opposite_levels.len() - 1);
+                }
+
+                // Ambele părți sunt exprimate în stake-ul backer-ului
+                let match_this_bet = matched_bet.unmatched_amount.clone().min(remaining.clone());
+
+                // Adăugăm partea matched pentru ambele pariuri, la cota nivelului din orderbook
+                bet.matched_parts.push(MatchedPart {
+                    amount: match_this_bet.clone(),
+                    odds: level_odds.clone()
+                });
+                matched_bet.matched_parts.push(MatchedPart {
+                    amount: match_this_bet.clone(),
+                    odds: level_odds.clone()
+                });
+
+                matched_bet.matched_amount += &match_this_bet;
+                matched_bet.unmatched_amount -= &match_this_bet;
+                matched_amount += &match_this_bet;
+                remaining -= &match_this_bet;
+                new_matches += &match_this_bet;
+                level_matched += &match_this_bet;
+
+                matched_bet.status = if matched_bet.unmatched_amount == BigUint::zero() {
+                    queue.remove_node_by_id(queue_node.get_node_id());
+                    self.bet_queue_node(nonce).clear();
+                    self.user_open_bets(&matched_bet.bettor).swap_remove(&nonce);
+                    self.user_matched_bets(&matched_bet.bettor).insert(nonce);
+                    BetStatus::Matched
+                } else {
+                    BetStatus::PartiallyMatched
+                };
+
+                // Recalculăm potential profit pentru pariul opus
+                matched_bet.potential_profit = self.calculate_total_potential_profit(&matched_bet);
+
+                // Rotunjirea pe fiecare parte poate lăsa liability-ul unui Lay în așteptare sub
+                // cel blocat; eliberăm diferența ca lock-ul să urmeze părțile matched
+                if matched_bet.bet_type == BetType::Lay {
+                    let required = self.calculate_required_collateral(&matched_bet);
+                    if required < matched_bet.liability {
+                        let released = &matched_bet.liability - &required;
+                        self.release_to_balance(&matched_bet.bettor, &matched_bet.payment_token, &released);
                     }
+                    matched_bet.liability = required;
+                }
+
+                self.bet_by_id(nonce).set(&matched_bet);
+            }
+
+            // Un pariu propriu anulat poate fi ultimul din nivel, caz în care nivelul e deja scos
+            if !self.level_node_id(market_id, selection_id, opposite_side, &level_odds).is_empty() {
+                if queue.is_empty() {
+                    self.remove_level(market_id, selection_id, opposite_side, &level_odds);
                 } else {
-                    i += 1;
+                    self.level_total_stake(market_id, selection_id, opposite_side, &level_odds)
+                        .update(|total| *total -= &level_matched);
                 }
-            } else {
-                i += 1;
             }
         }
-        
-        match bet.bet_type {
-            BetType::Back => self.selection_lay_levels(bet.event, bet.selection.id).set(&opposite_levels),
-            BetType::Lay => self.selection_back_levels(bet.event, bet.selection.id).set(&opposite_levels),
-        }
+
+        self.selection_liquidity(market_id, selection_id, opposite_side)
+            .update(|val| *val -= &new_matches);
     
         bet.matched_amount = matched_amount.clone();
         bet.unmatched_amount = remaining.clone();
@@ -120,19 +161,46 @@ pub trait TrackerModule:
     
         // Recalculăm profitul potențial bazat pe părțile matched
         bet.potential_profit = self.calculate_total_potential_profit(&bet);
+
+        // Un Lay matched la o cotă mai mică are nevoie de mai puțin liability
+        if bet.bet_type == BetType::Lay {
+            bet.liability = self.calculate_required_collateral(&bet);
+        }
     
-        if matched_amount > bet.matched_amount {
-            let new_matches = &matched_amount - &bet.matched_amount;
+        if new_matches > BigUint::zero() {
             self.update_total_matched(bet.event, bet.selection.id, &new_matches);
         }
         
-        if remaining > BigUint::zero() {
+        // Restul unui pariu oprit de self-trade prevention nu mai intră în orderbook
+        if cancel_incoming && remaining > BigUint::zero() {
+            self.release_unmatched(&mut bet);
+        } else if rest_unmatched && remaining > BigUint::zero() {
             self.add_to_orderbook(&bet);
         }
+        if bet.status == BetStatus::Matched {
+            self.index_user_bet(&bet);
+        }
     
-        self.bet_by_id(bet.nft_nonce).set(&bet);
+        self.bet_by_id(bet.id).set(&bet);
     
-        (matched_amount, remaining)
+        bet
+    }
+
+    /// A Back crosses every Lay level priced at or above its odds, a Lay every
+    /// Back level priced at or below.
+    fn crosses_level(&self, bet: &Bet<Self::Api>, level_odds: &BigUint) -> bool {
+        match bet.bet_type {
+            BetType::Back => level_odds >= &bet.odd,
+            BetType::Lay => level_odds <= &bet.odd,
+        }
+    }
+
+    /// Best price first: lowest odds for resting Backs, highest for resting Lays.
+    fn is_better_price(&self, bet_type: &BetType, odds: &BigUint, other: &BigUint) -> bool {
+        match bet_type {
+            BetType::Back => odds < other,
+            BetType::Lay => odds > other,
+        }
     }
 
     fn calculate_total_potential_profit(&self, bet: &Bet<Self::Api>) -> BigUint<Self::Api> {
@@ -142,9 +210,7 @@ pub trait TrackerModule:
             match bet.bet_type {
                 BetType::Back => {
                     // Pentru Back: amount * (odds - 1)
-                    let profit = (matched_part.odds.clone() - BigUint::from(100u32)) 
-                        * &matched_part.amount / BigUint::from(100u32);
-                    total_profit += profit;
+                    total_profit += self.calculate_liability(&matched_part.amount, &matched_part.odds);
                 },
                 BetType::Lay => {
                     // Pentru Lay: doar matched amount
@@ -156,137 +222,225 @@ pub trait TrackerModule:
         total_profit
     }
 
+    /// Liability a Lay has at risk on its matched parts.
+    fn calculate_matched_liability(&self, bet: &Bet<Self::Api>) -> BigUint<Self::Api> {
+        let mut matched_liability = BigUint::zero();
+        for matched_part in bet.matched_parts.iter() {
+            matched_liability += self.calculate_liability(&matched_part.amount, &matched_part.odds);
+        }
+        matched_liability
+    }
+
+    /// Collateral the contract holds for a bet: the stake for a Back, the liability for a Lay.
+    fn locked_collateral(&self, bet: &Bet<Self::Api>) -> BigUint<Self::Api> {
+        match bet.bet_type {
+            BetType::Back => bet.stake_amount.clone(),
+            BetType::Lay => bet.liability.clone(),
+        }
+    }
 
-    fn add_to_orderbook(&self, bet: &Bet<Self::Api>) {
-        let mut levels = match bet.bet_type {
-            BetType::Back => self.selection_back_levels(bet.event, bet.selection.id).get(),
-            BetType::Lay => self.selection_lay_levels(bet.event, bet.selection.id).get(),
+    /// Collateral a bet needs: matched parts at their own odds, the rest at the bet's odds.
+    fn calculate_required_collateral(&self, bet: &Bet<Self::Api>) -> BigUint<Self::Api> {
+        match bet.bet_type {
+            BetType::Back => bet.stake_amount.clone(),
+            BetType::Lay => {
+                self.calculate_matched_liability(bet)
+                    + self.calculate_liability(&bet.unmatched_amount, &bet.odd)
+            },
+        }
+    }
+
+    /// Drops the unmatched part of a bet and returns the collateral it frees.
+    /// The caller takes the bet out of the orderbook and refunds the amount.
+    fn release_unmatched(&self, bet: &mut Bet<Self::Api>) -> BigUint<Self::Api> {
+        let locked = self.locked_collateral(bet);
+
+        bet.unmatched_amount = BigUint::zero();
+        bet.stake_amount = bet.matched_amount.clone();
+        if bet.bet_type == BetType::Lay {
+            bet.liability = self.calculate_matched_liability(bet);
+        }
+        bet.status = if bet.matched_amount > BigUint::zero() {
+            BetStatus::Matched
+        } else {
+            BetStatus::Canceled
         };
+        self.index_user_bet(bet);
 
-        let mut level_index = Option::<usize>::None;
-        
-        for i in 0..levels.len() {
-            let level = levels.get(i);
-            if level.odds == bet.odd {
-                level_index = Some(i);
-                break;
-            }
+        locked - self.locked_collateral(bet)
+    }
+
+    /// Takes a resting bet out of the book and sends back the collateral of its
+    /// unmatched part.
+    fn lapse_unmatched(&self, bet_nonce: u64) {
+        let mut bet = self.bet_by_id(bet_nonce).get();
+        if bet.unmatched_amount == BigUint::zero() {
+            return;
         }
 
-        match level_index {
-            Some(i) => {
-                let mut level = levels.get(i);
-                level.total_stake += &bet.unmatched_amount;
-                level.bet_nonces.push(bet.nft_nonce);
-                let _ = levels.set(i, level);
-            },
-            None => {
-                let new_level = PriceLevel {
-                    odds: bet.odd.clone(),
-                    total_stake: bet.unmatched_amount.clone(),
-                    bet_nonces: ManagedVec::from_single_item(bet.nft_nonce),
-                };
+        self.remove_from_orderbook(&bet);
+        self.remove_exposure(&bet);
 
-                let mut insert_pos = levels.len();
-                for i in 0..levels.len() {
-                    let level = levels.get(i);
-                    match bet.bet_type {
-                        BetType::Back => {
-                            if bet.odd > level.odds {
-                                insert_pos = i;
-                                break;
-                            }
-                        },
-                        BetType::Lay => {
-                            if bet.odd < level.odds {
-                                insert_pos = i;
-                                break;
-                            }
-                        },
-                    }
-                }
+        // Actualizăm stake_amount și liability să reflecte doar partea matched
+        let refund_amount = self.release_unmatched(&mut bet);
+        self.add_exposure(&bet);
+        self.release_to_balance(&bet.bettor, &bet.payment_token, &refund_amount);
 
-                if insert_pos == levels.len() {
-                    levels.push(new_level);
-                } else {
-                    let mut temp_levels = ManagedVec::new();
-                    for i in 0..insert_pos {
-                        temp_levels.push(levels.get(i));
-                    }
-                    temp_levels.push(new_level);
-                    for i in insert_pos..levels.len() {
-                        temp_levels.push(levels.get(i));
-                    }
-                    levels = temp_levels;
-                }
-            }
+        self.bet_by_id(bet_nonce).set(&bet);
+        self.bet_refunded_event(bet_nonce, &bet.bettor, &refund_amount);
+    }
+
+    /// Resting bets of the same bettor are cancelled unless the owner picks another mode.
+    fn init_self_trade_prevention(&self) {
+        if self.self_trade_prevention().is_empty() {
+            self.self_trade_prevention().set(SelfTradePrevention::CancelResting);
         }
+    }
 
-        match bet.bet_type {
-            BetType::Back => {
-                self.selection_back_levels(bet.event, bet.selection.id).set(&levels);
-                self.selection_back_liquidity(bet.event, bet.selection.id)
-                    .update(|val| *val = levels.iter().fold(BigUint::zero(), |acc, level| acc + &level.total_stake));
-            },
-            BetType::Lay => {
-                self.selection_lay_levels(bet.event, bet.selection.id).set(&levels);
-                self.selection_lay_liquidity(bet.event, bet.selection.id)
-                    .update(|val| *val = levels.iter().fold(BigUint::zero(), |acc, level| acc + &level.total_stake));
-            },
+    #[only_owner]
+    #[endpoint(setSelfTradePrevention)]
+    fn set_self_trade_prevention(&self, mode: SelfTradePrevention) {
+        self.self_trade_prevention().set(mode);
+    }
+
+    #[view(getSelfTradePrevention)]
+    fn get_self_trade_prevention(&self) -> SelfTradePrevention {
+        self.self_trade_prevention().get()
+    }
+
+    /// Files the bet under its status in the bettor's indexes. A bet reaches a
+    /// settled status only once, so it is appended to the settled list once.
+    fn index_user_bet(&self, bet: &Bet<Self::Api>) {
+        if bet.status == BetStatus::Matched {
+            self.user_matched_bets(&bet.bettor).insert(bet.id);
+        } else if bet.status.is_settled() {
+            self.user_matched_bets(&bet.bettor).swap_remove(&bet.id);
+            self.user_settled_bets(&bet.bettor).push(&bet.id);
         }
     }
 
-    fn remove_from_orderbook(&self, bet: &Bet<Self::Api>) {
-        let mut levels = match bet.bet_type {
-            BetType::Back => self.selection_back_levels(bet.event, bet.selection.id).get(),
-            BetType::Lay => self.selection_lay_levels(bet.event, bet.selection.id).get(),
+    /// Counts a settled bet as won, lost or voided on its market.
+    fn count_settled_bet(&self, market_id: u64, status: &BetStatus) {
+        let mut counters = self.get_settlement_counters(market_id);
+        match status {
+            BetStatus::Win | BetStatus::HalfWon => counters.won += 1,
+            BetStatus::Lost | BetStatus::HalfLost => counters.lost += 1,
+            BetStatus::Push | BetStatus::Canceled => counters.voided += 1,
+            _ => {}
+        }
+        self.settlement_counters(market_id).set(counters);
+    }
+
+    #[view(getSettlementCounters)]
+    fn get_settlement_counters(&self, market_id: u64) -> SettlementCounters {
+        let counters = self.settlement_counters(market_id);
+        if counters.is_empty() {
+            SettlementCounters::default()
+        } else {
+            counters.get()
+        }
+    }
+
+    fn selection_liquidity(
+        &self,
+        market_id: u64,
+        selection_id: u64,
+        side: BetType
+    ) -> SingleValueMapper<BigUint> {
+        match side {
+            BetType::Back => self.selection_back_liquidity(market_id, selection_id),
+            BetType::Lay => self.selection_lay_liquidity(market_id, selection_id),
+        }
+    }
+
+    fn add_to_orderbook(&self, bet: &Bet<Self::Api>) {
+        let market_id = bet.event;
+        let selection_id = bet.selection.id;
+        let side = bet.bet_type;
+
+        if self.level_node_id(market_id, selection_id, side, &bet.odd).is_empty() {
+            self.insert_level(market_id, selection_id, side, &bet.odd);
+        }
+
+        let queue_node = self.level_queue(market_id, selection_id, side, &bet.odd)
+            .push_back(bet.id);
+        self.bet_queue_node(bet.id).set(queue_node.get_node_id());
+        self.user_open_bets(&bet.bettor).insert(bet.id);
+
+        self.level_total_stake(market_id, selection_id, side, &bet.odd)
+            .update(|total| *total += &bet.unmatched_amount);
+        self.selection_liquidity(market_id, selection_id, side)
+            .update(|val| *val += &bet.unmatched_amount);
+    }
+
+    /// Links a new level into the odds index. A price behind the whole book goes
+    /// to the back directly; any other is placed by walking at most
+    /// `MAX_LEVEL_DEPTH` levels from the best price, so dust levels cannot make
+    /// placement arbitrarily expensive.
+    fn insert_level(&self, market_id: u64, selection_id: u64, side: BetType, odds: &BigUint) {
+        let mut odds_index = self.selection_level_odds(market_id, selection_id, side);
+
+        let behind_book = match odds_index.back() {
+            Some(worst) => !self.is_better_price(&side, odds, worst.get_value_as_ref()),
+            None => true,
         };
-    
-        let mut level_index = Option::<usize>::None;
-        
-        for i in 0..levels.len() {
-            let level = levels.get(i);
-            if level.odds == bet.odd {
-                level_index = Some(i);
-                break;
-            }
+        let level_node = if behind_book {
+            odds_index.push_back(odds.clone())
+        } else {
+            let next_worse = odds_index
+                .iter()
+                .take(MAX_LEVEL_DEPTH)
+                .find(|node| self.is_better_price(&side, odds, node.get_value_as_ref()))
+                .unwrap_or_else(|| sc_panic!("Price too deep in the orderbook"));
+            odds_index
+                .push_before_node_id(next_worse.get_node_id(), odds.clone())
+                .unwrap_or_else(|| sc_panic!("Invalid price level"))
+        };
+        self.level_node_id(market_id, selection_id, side, odds).set(level_node.get_node_id());
+    }
+
+    fn remove_level(&self, market_id: u64, selection_id: u64, side: BetType, odds: &BigUint) {
+        let level_node_id = self.level_node_id(market_id, selection_id, side, odds);
+        self.selection_level_odds(market_id, selection_id, side)
+            .remove_node_by_id(level_node_id.get());
+        level_node_id.clear();
+        self.level_total_stake(market_id, selection_id, side, odds).clear();
+    }
+
+    fn remove_from_orderbook(&self, bet: &Bet<Self::Api>) {
+        let market_id = bet.event;
+        let selection_id = bet.selection.id;
+        let side = bet.bet_type;
+
+        let queue_node_id = self.bet_queue_node(bet.id);
+        if queue_node_id.is_empty() {
+            return;
         }
-    
-        if let Some(i) = level_index {
-            let mut level = levels.get(i);
-            level.total_stake -= &bet.unmatched_amount;
-            
-            let mut updated_nonces = ManagedVec::new();
-            for nonce in level.bet_nonces.iter() {
-                if nonce != bet.nft_nonce {
-                    updated_nonces.push(nonce);
-                }
-            }
-            
-            if updated_nonces.is_empty() {
-                if i < levels.len() - 1 {
-                    let last = levels.get(levels.len() - 1);
-                    let _ = levels.set(i, last);
-                }
-                levels.remove(levels.len() - 1);
-            } else {
-                level.bet_nonces = updated_nonces;
-                let _ = levels.set(i, level);
-            }
-            
-            match bet.bet_type {
-                BetType::Back => {
-                    self.selection_back_levels(bet.event, bet.selection.id).set(&levels);
-                    self.selection_back_liquidity(bet.event, bet.selection.id)
-                        .update(|val| *val -= &bet.unmatched_amount);
-                },
-                BetType::Lay => {
-                    self.selection_lay_levels(bet.event, bet.selection.id).set(&levels);
-                    self.selection_lay_liquidity(bet.event, bet.selection.id)
-                        .update(|val| *val -= &bet.unmatched_amount);
-                },
-            }
+
+        let mut queue = self.level_queue(market_id, selection_id, side, &bet.odd);
+        queue.remove_node_by_id(queue_node_id.get());
+        queue_node_id.clear();
+        self.user_open_bets(&bet.bettor).swap_remove(&bet.id);
+
+        if queue.is_empty() {
+            self.remove_level(market_id, selection_id, side, &bet.odd);
+        } else {
+            self.level_total_stake(market_id, selection_id, side, &bet.odd)
+                .update(|total| *total -= &bet.unmatched_amount);
         }
+
+        self.selection_liquidity(market_id, selection_id, side)
+            .update(|val| *val -= &bet.unmatched_amount);
+    }
+
+    /// Shrinks a resting bet in place so it keeps its place in the queue.
+    fn reduce_in_orderbook(&self, bet: &Bet<Self::Api>, amount: &BigUint) {
+        require!(!self.bet_queue_node(bet.id).is_empty(), "Bet not found in orderbook");
+
+        self.level_total_stake(bet.event, bet.selection.id, bet.bet_type, &bet.odd)
+            .update(|total| *total -= amount);
+        self.selection_liquidity(bet.event, bet.selection.id, bet.bet_type)
+            .update(|val| *val -= amount);
     }
 
     fn update_total_matched(
@@ -297,14 +451,22 @@ pub trait TrackerModule:
     ) {
         self.total_matched_amount(market_id, selection_id)
             .update(|total| *total += matched_amount);
+        self.market_total_matched(market_id)
+            .update(|total| *total += matched_amount);
     }
 
-    fn count_valid_bets_at_level(&self, level: &PriceLevel<Self::Api>) -> u32 {
+    fn count_valid_bets_at_level(
+        &self,
+        market_id: u64,
+        selection_id: u64,
+        side: BetType,
+        odds: &BigUint
+    ) -> u32 {
         let mut count = 0u32;
         let mut processed_bettors = ManagedVec::<Self::Api, ManagedAddress<Self::Api>>::new();
         
-        for nonce in level.bet_nonces.iter() {
-            let bet = self.bet_by_id(nonce).get();
+        for node in self.level_queue(market_id, selection_id, side, odds).iter() {
+            let bet = self.bet_by_id(node.into_value()).get();
             if bet.unmatched_amount > BigUint::zero() {
                 let mut is_unique = true;
                 for processed_bettor in processed_bettors.iter() {
@@ -322,76 +484,109 @@ pub trait TrackerModule:
         count
     }
 
+    fn get_price_level_views(
+        &self,
+        market_id: u64,
+        selection_id: u64,
+        side: BetType
+    ) -> ManagedVec<Self::Api, PriceLevelView<Self::Api>> {
+        let mut level_views = ManagedVec::new();
+        for level_node in self.selection_level_odds(market_id, selection_id, side).iter() {
+            let odds = level_node.into_value();
+            let mut bets = ManagedVec::new();
+            for queue_node in self.level_queue(market_id, selection_id, side, &odds).iter() {
+                let nonce = queue_node.into_value();
+                let bet = self.bet_by_id(nonce).get();
+                bets.push(BetView {
+                    nonce,
+                    bettor: bet.bettor,
+                    stake: bet.stake_amount,
+                    matched: bet.matched_amount,
+                    unmatched: bet.unmatched_amount,
+                    status: bet.status
+                });
+            }
+
+            level_views.push(PriceLevelView {
+                total_stake: self.level_total_stake(market_id, selection_id, side, &odds).get(),
+                odds,
+                bets
+            });
+        }
+        level_views
+    }
+
     #[view(getMatchingDetails)]
     fn get_matching_details(
         &self,
         market_id: u64,
         selection_id: u64
     ) -> MatchingDetails<Self::Api> {
-        let back_levels = self.selection_back_levels(market_id, selection_id).get();
-        let lay_levels = self.selection_lay_levels(market_id, selection_id).get();
-        let back_liquidity = self.selection_back_liquidity(market_id, selection_id).get();
-        let lay_liquidity = self.selection_lay_liquidity(market_id, selection_id).get();
-        let matched_count = self.selection_matched_count(market_id, selection_id).get();
-        let unmatched_count = self.selection_unmatched_count(market_id, selection_id).get();
-        let partially_matched_count = self.selection_partially_matched_count(market_id, selection_id).get();
-
-        let mut back_level_views = ManagedVec::new();
-        for level in back_levels.iter() {
-            if level.total_stake > BigUint::zero() && !level.bet_nonces.is_empty() {
-                let mut bets = ManagedVec::new();
-                for nonce in level.bet_nonces.iter() {
-                    let bet = self.bet_by_id(nonce).get();
-                    bets.push(BetView {
-                        nonce,
-                        bettor: bet.bettor,
-                        stake: bet.stake_amount,
-                        matched: bet.matched_amount,
-                        unmatched: bet.unmatched_amount,
-                        status: bet.status
-                    });
-                }
-                
-                back_level_views.push(PriceLevelView {
-                    odds: level.odds,
-                    total_stake: level.total_stake,
-                    bets
-                });
-            }
+        MatchingDetails {
+            back_levels: self.get_price_level_views(market_id, selection_id, BetType::Back),
+            lay_levels: self.get_price_level_views(market_id, selection_id, BetType::Lay),
+            back_liquidity: self.selection_back_liquidity(market_id, selection_id).get(),
+            lay_liquidity: self.selection_lay_liquidity(market_id, selection_id).get(),
+            matched_count: self.selection_matched_count(market_id, selection_id).get(),
+            unmatched_count: self.selection_unmatched_count(market_id, selection_id).get(),
+            partially_matched_count: self.selection_partially_matched_count(market_id, selection_id).get()
         }
+    }
 
-        let mut lay_level_views = ManagedVec::new();
-        for level in lay_levels.iter() {
-            if level.total_stake > BigUint::zero() && !level.bet_nonces.is_empty() {
-                let mut bets = ManagedVec::new();
-                for nonce in level.bet_nonces.iter() {
-                    let bet = self.bet_by_id(nonce).get();
-                    bets.push(BetView {
-                        nonce,
-                        bettor: bet.bettor,
-                        stake: bet.stake_amount,
-                        matched: bet.matched_amount,
-                        unmatched: bet.unmatched_amount,
-                        status: bet.status
-                    });
-                }
-                
-                lay_level_views.push(PriceLevelView {
-                    odds: level.odds,
-                    total_stake: level.total_stake,
-                    bets
-                });
+    /// The price levels of `getMatchingDetails` with every price in all formats.
+    #[view(getFormattedLadder)]
+    fn get_formatted_ladder(
+        &self,
+        market_id: u64,
+        selection_id: u64
+    ) -> FormattedLadder<Self::Api> {
+        FormattedLadder {
+            back_levels: self.get_formatted_levels(market_id, selection_id, BetType::Back),
+            lay_levels: self.get_formatted_levels(market_id, selection_id, BetType::Lay),
+        }
+    }
+
+    fn get_formatted_levels(
+        &self,
+        market_id: u64,
+        selection_id: u64,
+        side: BetType
+    ) -> ManagedVec<Self::Api, FormattedPriceLevel<Self::Api>> {
+        let mut levels = ManagedVec::new();
+        for level_node in self.selection_level_odds(market_id, selection_id, side).iter() {
+            let odds = level_node.into_value();
+            levels.push(FormattedPriceLevel {
+                odds: self.get_odds_formats(odds.to_u64().unwrap_or_default()),
+                total_stake: self.level_total_stake(market_id, selection_id, side, &odds).get(),
+            });
+        }
+        levels
+    }
+
+    #[view(getQueuePosition)]
+    fn get_queue_position(
+        &self,
+        bet_nonce: u64
+    ) -> QueuePosition<Self::Api> {
+        let bet = self.bet_by_id(bet_nonce).get();
+        require!(!self.bet_queue_node(bet_nonce).is_empty(), "Bet is not in the orderbook");
+
+        let mut bets_ahead = 0u32;
+        let mut stake_ahead = BigUint::zero();
+        for node in self.level_queue(bet.event, bet.selection.id, bet.bet_type, &bet.odd).iter() {
+            let nonce = node.into_value();
+            if nonce == bet_nonce {
+                break;
             }
+            bets_ahead += 1;
+            stake_ahead += self.bet_by_id(nonce).get().unmatched_amount;
         }
 
-        MatchingDetails {
-            back_levels: back_level_views,
-            lay_levels: lay_level_views,
-            back_liquidity,
-            lay_liquidity,
-            matched_count,
-            unmatched_count,
-            partially_matched_count
+        QueuePosition {
+            level_total_stake: self.level_total_stake(bet.event, bet.selection.id, bet.bet_type, &bet.odd).get(),
+            odds: bet.odd,
+            bets_ahead,
+            stake_ahead
         }
     }
 
@@ -419,7 +614,7 @@ pub trait TrackerModule:
     ) -> BetView<Self::Api> {
         let bet = self.bet_by_id(bet_nonce).get();
         BetView {
-            nonce: bet.nft_nonce,
+            nonce: bet.id,
             bettor: bet.bettor,
             stake: bet.stake_amount,
             matched: bet.matched_amount,
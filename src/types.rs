@@ -10,6 +10,40 @@ pub enum BetStatus {
     Win,
     Lost,
     Canceled,
+    // Jumătate câștigată, jumătate returnată
+    HalfWon,
+    // Miza returnată integral
+    Push,
+    // Jumătate pierdută, jumătate returnată
+    HalfLost,
+}
+
+impl BetStatus {
+    /// Status of a settled bet from the shares of its stake, in basis points,
+    /// that won, were returned and lost. A dead-heat, with a share won and the
+    /// rest lost, counts as a win.
+    pub fn from_outcome(won_bps: u64, void_bps: u64, lost_bps: u64) -> Self {
+        match (won_bps, void_bps, lost_bps) {
+            (0, 0, _) => BetStatus::Lost,
+            (_, 0, 0) => BetStatus::Win,
+            (0, _, 0) => BetStatus::Push,
+            (_, _, 0) => BetStatus::HalfWon,
+            (0, _, _) => BetStatus::HalfLost,
+            _ => BetStatus::Win,
+        }
+    }
+
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self,
+            BetStatus::Win
+                | BetStatus::Lost
+                | BetStatus::Canceled
+                | BetStatus::HalfWon
+                | BetStatus::Push
+                | BetStatus::HalfLost
+        )
+    }
 }
 
 #[type_abi]
@@ -19,12 +53,60 @@ pub enum BetType {
     Lay
 }
 
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum TimeInForce {
+    GoodTillClose,
+    ImmediateOrCancel,
+    FillOrKill
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum BetPersistence {
+    Lapse,
+    KeepInPlay
+}
+
+/// What happens when an incoming bet would match a resting bet of the same bettor.
+/// `CancelResting` is the default and must stay first: it encodes as empty storage.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum SelfTradePrevention {
+    CancelResting,
+    CancelIncoming,
+    Skip
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum BetStatusFilter {
+    All,
+    Open,
+    Matched,
+    Settled
+}
+
+impl BetType {
+    pub fn opposite(&self) -> Self {
+        match self {
+            BetType::Back => BetType::Lay,
+            BetType::Lay => BetType::Back,
+        }
+    }
+}
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq)]
 pub enum MarketStatus {
     Open,    
+    InPlay,
     Closed, 
-    Settled
+    Settled,
+    // Toate pariurile au fost decontate
+    Paid,
+    // Piață anulată, toate mizele se returnează
+    Voided
 }
 
 #[type_abi]
@@ -38,9 +120,10 @@ pub struct MatchedPart<M: ManagedTypeApi> {
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
 pub struct Bet<M: ManagedTypeApi> {
+    pub id: u64,
     pub bettor: ManagedAddress<M>,
     pub event: u64, 
-    pub selection: Selection<M>, 
+    pub selection: Selection, 
     pub stake_amount: BigUint<M>, 
     pub liability: BigUint<M>, 
     pub matched_amount: BigUint<M>, 
@@ -50,17 +133,29 @@ pub struct Bet<M: ManagedTypeApi> {
     pub odd: BigUint<M>, 
     pub bet_type: BetType, 
     pub status: BetStatus, 
+    pub persistence: BetPersistence,
     pub payment_token: EgldOrEsdtTokenIdentifier<M>,
     pub payment_nonce: u64,
     pub nft_nonce: u64,
     pub created_at: u64, 
 }
 
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
+pub struct PlacedBet<M: ManagedTypeApi> {
+    pub bet_id: u64,
+    pub nft_nonce: u64,
+    pub matched_amount: BigUint<M>,
+    pub unmatched_amount: BigUint<M>,
+    pub refunded_amount: BigUint<M>,
+    pub status: BetStatus,
+}
+
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
 pub struct BetAttributes<M:ManagedTypeApi>{
     // pub bettor: ManagedAddress<M>,
     pub event: u64,     
-    pub selection: Selection<M>,     
+    pub selection: Selection,     
     pub stake: BigUint<M>, 
     // pub liability: BigUint<M>,  
     // pub matched_amount: BigUint<M>, 
@@ -75,40 +170,100 @@ pub struct BetAttributes<M:ManagedTypeApi>{
     // pub created_at: u64, 
 }
 
+/// A selection that did not simply lose, with the shares of the stakes on it
+/// that win and that are returned, in basis points: 10_000/0 for an outright
+/// winner, 5_000/0 for a two-way dead-heat, 0/10_000 for a push, 5_000/5_000
+/// for a half-win and 0/5_000 for a half-loss. The remaining share loses.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone, PartialEq)]
+pub struct WinningSelection {
+    pub selection_id: u64,
+    pub win_fraction_bps: u64,
+    pub void_fraction_bps: u64,
+}
+
+/// Settled bets of a market: won and half-won, lost and half-lost, pushed or voided.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Default)]
+pub struct SettlementCounters {
+    pub won: u64,
+    pub lost: u64,
+    pub voided: u64,
+}
+
+/// A user's stakes on one selection, with unmatched parts counted as if matched
+/// at their own odds. `back_profit` is what the backs win and `lay_liability`
+/// what the lays lose if the selection wins.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct ExposurePosition<M: ManagedTypeApi> {
+    pub back_stake: BigUint<M>,
+    pub back_profit: BigUint<M>,
+    pub lay_stake: BigUint<M>,
+    pub lay_liability: BigUint<M>,
+}
+
+/// Stake bounds in the token's smallest unit
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct StakeLimits<M: ManagedTypeApi> {
+    pub min_stake: BigUint<M>,
+    pub max_stake: BigUint<M>,
+}
+
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
 pub struct Market<M: ManagedTypeApi> {
     pub market_id: u64,
     pub event_id: u64,
     pub description: ManagedBuffer<M>,
-    pub selections: ManagedVec<M, Selection<M>>,
+    pub selections: ManagedVec<M, Selection>,
     pub close_timestamp: u64,
+    pub token: EgldOrEsdtTokenIdentifier<M>,
     pub market_status: MarketStatus,
-    pub total_matched_amount: BigUint<M>,
+    pub in_play: bool,
     pub liquidity: BigUint<M>,
     pub created_at: u64,
 }
 
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
-pub struct Selection<M: ManagedTypeApi> {
+pub struct Selection {
     pub id: u64,
     pub value: u64,
-    pub priority_queue: Tracker<M>,
 }
 
-#[type_abi]
+// Layout-ul din prima versiune a contractului, citit doar de migrateLegacyState
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq)]
+pub enum LegacyMarketStatus {
+    Open,
+    Closed,
+    Settled
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct LegacyMarket<M: ManagedTypeApi> {
+    pub market_id: u64,
+    pub event_id: u64,
+    pub description: ManagedBuffer<M>,
+    pub selections: ManagedVec<M, LegacySelection<M>>,
+    pub close_timestamp: u64,
+    pub market_status: LegacyMarketStatus,
+    pub total_matched_amount: BigUint<M>,
+    pub liquidity: BigUint<M>,
+    pub created_at: u64,
+}
+
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
-pub struct PriceLevel<M: ManagedTypeApi> {
-    pub odds: BigUint<M>,
-    pub total_stake: BigUint<M>,
-    pub bet_nonces: ManagedVec<M, u64>,
+pub struct LegacySelection<M: ManagedTypeApi> {
+    pub id: u64,
+    pub value: u64,
+    pub priority_queue: LegacyTracker<M>,
 }
 
-#[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
-pub struct Tracker<M: ManagedTypeApi> {
-    pub back_levels: ManagedVec<M, PriceLevel<M>>,
-    pub lay_levels: ManagedVec<M, PriceLevel<M>>,
+pub struct LegacyTracker<M: ManagedTypeApi> {
+    pub back_levels: ManagedVec<M, LegacyPriceLevel<M>>,
+    pub lay_levels: ManagedVec<M, LegacyPriceLevel<M>>,
     pub back_liquidity: BigUint<M>,
     pub lay_liquidity: BigUint<M>,
     pub matched_count: u64,
@@ -119,37 +274,123 @@ pub struct Tracker<M: ManagedTypeApi> {
     pub canceled_count: u64,
 }
 
-#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone)]
-pub enum MarketType {
-    FullTimeResult = 1,
-    TotalGoals = 2,
-    BothTeamsToScore = 3,
-}
-
-impl MarketType {
-    pub fn from_u64(value: u64) -> Self {
-        match value {
-            1 => MarketType::FullTimeResult,
-            2 => MarketType::TotalGoals,
-            3 => MarketType::BothTeamsToScore,
-            _ => panic!("Invalid market type")
-        }
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
+pub struct LegacyPriceLevel<M: ManagedTypeApi> {
+    pub odds: BigUint<M>,
+    pub total_stake: BigUint<M>,
+    pub bet_nonces: ManagedVec<M, u64>,
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct LegacyBet<M: ManagedTypeApi> {
+    pub bettor: ManagedAddress<M>,
+    pub event: u64,
+    pub selection: LegacySelection<M>,
+    pub stake_amount: BigUint<M>,
+    pub liability: BigUint<M>,
+    pub matched_amount: BigUint<M>,
+    pub matched_parts: ManagedVec<M, MatchedPart<M>>,
+    pub unmatched_amount: BigUint<M>,
+    pub potential_profit: BigUint<M>,
+    pub odd: BigUint<M>,
+    pub bet_type: BetType,
+    pub status: BetStatus,
+    pub payment_token: EgldOrEsdtTokenIdentifier<M>,
+    pub payment_nonce: u64,
+    pub nft_nonce: u64,
+    pub created_at: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct OddsMigrationProgress {
+    pub current_scale: u64,
+    pub target_scale: u64,
+    pub bets_migrated: u64,
+    pub total_bets: u64,
+    pub markets_migrated: u64,
+    pub total_markets: u64,
+}
+
+/// Prices above the previous band's `up_to` and up to this one move in steps of `tick`
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
+pub struct OddsBand {
+    pub up_to: u64,
+    pub tick: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum EventStatus {
+    Scheduled,
+    Live,
+    Finished,
+    Cancelled,
+}
+
+impl EventStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(self, EventStatus::Finished | EventStatus::Cancelled)
     }
 
-    pub fn to_description(&self) -> &[u8] {
-        match self {
-            MarketType::FullTimeResult => b"FullTime Result",
-            MarketType::TotalGoals => b"Total Goals O/U 2.5",
-            MarketType::BothTeamsToScore => b"Both Teams To Score",
-        }
+    /// Un eveniment doar avansează: Scheduled, Live, Finished; Cancelled din orice stare nefinală
+    pub fn can_move_to(&self, next: &EventStatus) -> bool {
+        matches!(
+            (self, next),
+            (EventStatus::Scheduled, EventStatus::Live)
+                | (EventStatus::Scheduled | EventStatus::Live, EventStatus::Finished | EventStatus::Cancelled)
+        )
     }
 }
 
+/// A sporting event markets are created on.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct Event<M: ManagedTypeApi> {
+    pub event_id: u64,
+    pub sport: ManagedBuffer<M>,
+    pub competition: ManagedBuffer<M>,
+    pub home: ManagedBuffer<M>,
+    pub away: ManagedBuffer<M>,
+    pub start_timestamp: u64,
+    pub status: EventStatus,
+}
+
+/// Regula după care un tip de piață își alege selecțiile câștigătoare.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum MarketKind {
+    FullTimeResult,
+    OverUnder,
+    AsianHandicap,
+    EuropeanHandicap,
+    DoubleChance,
+    DrawNoBet,
+    BothTeamsToScore,
+    CorrectScore,
+    HalfTimeFullTime,
+    WinningMargin,
+}
+
+/// A market type registered in the catalogue. For Over/Under and the handicaps
+/// `line` is in hundredths of a goal (250 is 2.5, -150 is -1.5, applied to the
+/// home team); for Correct Score and Winning Margin it is the highest score or
+/// margin with its own selection.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct MarketType<M: ManagedTypeApi> {
+    pub kind: MarketKind,
+    pub line: i64,
+    pub description: ManagedBuffer<M>,
+}
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone)]
 pub enum ProcessingStatus {
     InProgress,
-    Completed
+    Completed,
+    NotStarted
 }
 
 #[type_abi]
@@ -157,6 +398,7 @@ pub enum ProcessingStatus {
 pub struct ProcessingProgress {
     pub market_id: u64,
     pub processed_bets: u64,
+    pub total_bets: u64,
     pub status: ProcessingStatus
 }
 
@@ -172,6 +414,42 @@ pub struct BetMatchingState<M: ManagedTypeApi> {
     pub odds: BigUint<M>
 }
 
+/// Odds as quoted outside the contract. Decimal odds are in odds scale units,
+/// American odds are the moneyline (+150, -200) and the implied probability is
+/// in basis points.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub enum OddsInput {
+    Decimal(u64),
+    Fractional(u64, u64),
+    American(i64),
+    ImpliedProbability(u64)
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, ManagedVecItem)]
+pub struct OddsFormats {
+    pub decimal: u64,
+    pub fractional_numerator: u64,
+    pub fractional_denominator: u64,
+    pub american: i64,
+    pub implied_probability_bps: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem)]
+pub struct FormattedPriceLevel<M: ManagedTypeApi> {
+    pub odds: OddsFormats,
+    pub total_stake: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct FormattedLadder<M: ManagedTypeApi> {
+    pub back_levels: ManagedVec<M, FormattedPriceLevel<M>>,
+    pub lay_levels: ManagedVec<M, FormattedPriceLevel<M>>,
+}
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem)]
 pub struct MatchingDetails<M: ManagedTypeApi> {
@@ -192,6 +470,24 @@ pub struct PriceLevelView<M: ManagedTypeApi> {
     pub bets: ManagedVec<M, BetView<M>>
 }
 
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem)]
+pub struct UserBetView<M: ManagedTypeApi> {
+    pub nonce: u64,
+    pub market_id: u64,
+    pub selection_id: u64,
+    pub bet_type: BetType,
+    pub odds: BigUint<M>,
+    pub stake: BigUint<M>,
+    pub matched: BigUint<M>,
+    pub unmatched: BigUint<M>,
+    pub liability: BigUint<M>,
+    pub potential_profit: BigUint<M>,
+    pub status: BetStatus,
+    pub nft_nonce: u64,
+    pub created_at: u64
+}
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem)]
 pub struct BetView<M: ManagedTypeApi> {
@@ -203,6 +499,15 @@ pub struct BetView<M: ManagedTypeApi> {
     pub status: BetStatus
 }
 
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct QueuePosition<M: ManagedTypeApi> {
+    pub odds: BigUint<M>,
+    pub bets_ahead: u32,
+    pub stake_ahead: BigUint<M>,
+    pub level_total_stake: BigUint<M>
+}
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, ManagedVecItem, Debug)]
 pub struct OrderbookView<M: ManagedTypeApi> {
@@ -211,8 +516,6 @@ pub struct OrderbookView<M: ManagedTypeApi> {
     pub bet_count: u32
 }
 
-///
-///
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem)]
 pub struct DebugMatchedPart<M: ManagedTypeApi> {
@@ -1,5 +1,4 @@
-use crate::constants::constants;
-use crate::types::{Market, MarketStatus};
+use crate::types::{EventStatus, Market, MarketStatus, StakeLimits};
 
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
@@ -7,33 +6,67 @@ multiversx_sc::derive_imports!();
 #[multiversx_sc::module]
 pub trait ValidationModule:
     crate::storage::StorageModule +
-    crate::events::EventsModule
+    crate::events::EventsModule +
+    crate::odds::OddsModule
 {
     //--------------------------------------------------------------------------------------------//
     //-------------------------------- Bet Validation --------------------------------------------//
     //--------------------------------------------------------------------------------------------//    
 
-    fn validate_bet_amount(&self, total_amount: &BigUint) {
-        let one_token = BigUint::from(1_000_000_000_000_000_000u64);
-        let tokens = total_amount / &one_token;
+    fn validate_bet_amount(
+        &self,
+        stake: &BigUint,
+        market_id: u64,
+        token: &EgldOrEsdtTokenIdentifier
+    ) {
+        let limits = self.get_effective_stake_limits(market_id, token);
+        require!(stake >= &limits.min_stake, "Stake amount out of range");
+        require!(stake <= &limits.max_stake, "Stake amount out of range");
+    }
 
-        require!(
-            tokens >= BigUint::from(1u32) && tokens <= BigUint::from(10000u32),
-            "Stake amount out of range"
-        );
+    /// The token's limits, narrowed by the market's own limits when it has any.
+    fn get_effective_stake_limits(
+        &self,
+        market_id: u64,
+        token: &EgldOrEsdtTokenIdentifier
+    ) -> StakeLimits<Self::Api> {
+        require!(!self.token_stake_limits(token).is_empty(), "Stake limits not set for token");
+        let mut limits = self.token_stake_limits(token).get();
+
+        let market_limits = self.market_stake_limits(market_id, token);
+        if !market_limits.is_empty() {
+            let market_limits = market_limits.get();
+            limits.min_stake = limits.min_stake.max(market_limits.min_stake);
+            limits.max_stake = limits.max_stake.min(market_limits.max_stake);
+            // Limitele tokenului se pot schimba după ce au fost setate cele ale pieței
+            require!(limits.min_stake <= limits.max_stake, "Market stake limits outside token limits");
+        }
+        limits
+    }
+
+    /// Bets are paid, refunded and settled only in the market's fungible token.
+    fn validate_payment_token(
+        &self,
+        market_id: u64,
+        token: &EgldOrEsdtTokenIdentifier,
+        token_nonce: u64
+    ) {
+        let market = self.markets(market_id).get();
+        require!(token == &market.token, "Invalid payment token");
+        require!(token_nonce == 0, "Invalid payment token nonce");
     }
 
     fn validate_bet_odds(&self, odds: &BigUint) {
-        require!(
-            odds >= &BigUint::from(101u32) && odds <= &BigUint::from(100000u32),
-            "Odds out of range"
-        );
+        require!(self.is_on_odds_ladder(odds), "Odds not on ladder");
     }
     
+    /// For a Lay the payment is the layer's liability; the bet itself is sized in
+    /// backer's stake, the unit every match is expressed in.
     fn validate_lay_bet(&self, total_amount: &BigUint, odds: &BigUint) -> (BigUint, BigUint) {
-        let odds_minus_one = odds - &BigUint::from(100u32);
-        let stake = total_amount.clone();
-        let calculated_liability = (stake.clone() * odds_minus_one) / &BigUint::from(100u32);
+        let scale = BigUint::from(self.odds_scale().get());
+        let odds_minus_one = odds - &scale;
+        let stake = (total_amount * &scale) / &odds_minus_one;
+        let calculated_liability = self.calculate_liability(&stake, odds);
         
         require!(calculated_liability > BigUint::zero(), "Invalid liability calculation");
         
@@ -44,12 +77,27 @@ pub trait ValidationModule:
         (total_amount.clone(), BigUint::zero())
     }
 
+    /// Layer's liability for a backer's stake matched at `odds`. This is also the
+    /// backer's profit if the selection wins, so both legs of a match agree.
+    fn calculate_liability(&self, stake: &BigUint, odds: &BigUint) -> BigUint {
+        let scale = BigUint::from(self.odds_scale().get());
+        stake * &(odds - &scale) / &scale
+    }
+
     //--------------------------------------------------------------------------------------------//
     //-------------------------------- Market Validation (FOR ADMIN) -----------------------------//
     //--------------------------------------------------------------------------------------------//
     
-    fn validate_market_creation(&self, close_timestamp: u64) {
+    fn validate_market_creation(&self, event_id: u64, close_timestamp: u64) {
         self.validate_market_timestamp(close_timestamp);
+
+        require!(!self.events(event_id).is_empty(), "Invalid event");
+        let event = self.events(event_id).get();
+        require!(event.status == EventStatus::Scheduled, "Event not scheduled");
+        require!(
+            close_timestamp <= event.start_timestamp,
+            "Market must close by event start"
+        );
     }
 
     fn validate_market_timestamp(&self, close_timestamp: u64) {
@@ -74,12 +122,25 @@ pub trait ValidationModule:
     //-------------------------------- Market Validation (FOR USER) -------------------------------//
     //---------------------------------------------------------------------------------------------//
 
+    fn require_owner_or_oracle(&self) {
+        let caller = self.blockchain().get_caller();
+        let is_oracle = !self.oracle().is_empty() && self.oracle().get() == caller;
+        require!(
+            is_oracle || caller == self.blockchain().get_owner_address(),
+            "Only owner or oracle"
+        );
+    }
+
     fn validate_market(&self, market_id: u64) {
         require!(!self.markets(market_id).is_empty(), "Invalid market");
         
         let market = self.markets(market_id).get();
+        if market.market_status == MarketStatus::InPlay {
+            return;
+        }
+
         let created_at = self.blockchain().get_block_timestamp();
-        
+        require!(market.market_status == MarketStatus::Open, "Market not open");
         require!(created_at < market.close_timestamp, "Market already closed");
     }
 
@@ -93,25 +154,11 @@ pub trait ValidationModule:
         require!(selection_exists, "Invalid selection");
     }
 
-    fn validate_user_exposure(
-        &self,
-        user: &ManagedAddress<Self::Api>,
-        stake: &BigUint
-    ) {
-        let current_exposure = self.user_total_exposure(user).get();
-        let new_exposure = &current_exposure + stake;
-        
-        require!(
-            new_exposure <= BigUint::from(constants::MAX_USER_EXPOSURE),
-            "Maximum stake exceeded"
-        );
-    }
-
     fn get_next_market_id(&self) -> u64 {
         let mut counter = self.market_counter().get();
         counter += 1;
         
-        self.market_counter().set(&counter);
+        self.market_counter().set(counter);
         counter
     }
 }
\ No newline at end of file
@@ -0,0 +1,2933 @@
+use multiversx_sc_scenario::imports::*;
+
+use rockstake::bet::BetModule;
+use rockstake::catalogue::CatalogueModule;
+use rockstake::exposure::ExposureModule;
+use rockstake::fund::FundModule;
+use rockstake::ledger::LedgerModule;
+use rockstake::market::MarketModule;
+use rockstake::migration::MigrationModule;
+use rockstake::odds::OddsModule;
+use rockstake::storage::StorageModule;
+use rockstake::tracker::TrackerModule;
+use rockstake::types::{BetPersistence, BetStatus, BetStatusFilter, BetType, EventStatus, LegacyBet, LegacyMarket, LegacyMarketStatus, LegacyPriceLevel, LegacySelection, LegacyTracker, MarketKind, MarketStatus, MarketType, OddsInput, ProcessingStatus, SelfTradePrevention, TimeInForce};
+use rockstake::Rockstake;
+
+const OWNER: TestAddress = TestAddress::new("owner");
+const BACKER: TestAddress = TestAddress::new("backer");
+const SECOND_BACKER: TestAddress = TestAddress::new("second-backer");
+const LAYER: TestAddress = TestAddress::new("layer");
+const SECOND_LAYER: TestAddress = TestAddress::new("second-layer");
+const ROCKSTAKE: TestSCAddress = TestSCAddress::new("rockstake");
+const CODE_PATH: MxscPath = MxscPath::new("output/rockstake.mxsc.json");
+const BET_TOKEN: TestTokenIdentifier = TestTokenIdentifier::new("BET-123456");
+const STABLE_TOKEN: TestTokenIdentifier = TestTokenIdentifier::new("USDC-123456");
+
+const ONE_EGLD: u128 = 1_000_000_000_000_000_000;
+const STARTING_BALANCE: u128 = 1_000 * ONE_EGLD;
+const CLOSE_TIMESTAMP: u64 = 1_000;
+const EVENT_ID: u64 = 1;
+const SECOND_EVENT_ID: u64 = 2;
+const MARKET_ID: u64 = 1;
+const HOME: u64 = 1;
+
+fn egld(amount: u128) -> u128 {
+    amount * ONE_EGLD
+}
+
+/// Cotele din teste sunt scrise în sutimi, ca pe bilet
+fn odds(hundredths: u64) -> u64 {
+    hundredths * 100
+}
+
+fn balance(amount: u128) -> BigUint<StaticApi> {
+    BigUint::from(amount)
+}
+
+fn setup() -> ScenarioWorld {
+    let mut world = ScenarioWorld::new();
+    world.register_contract(CODE_PATH, rockstake::ContractBuilder);
+
+    world.account(OWNER).nonce(1);
+    for bettor in [BACKER, SECOND_BACKER, LAYER, SECOND_LAYER] {
+        world.account(bettor).nonce(1).balance(balance(STARTING_BALANCE));
+    }
+    world
+        .account(ROCKSTAKE)
+        .nonce(1)
+        .code(CODE_PATH)
+        .owner(OWNER)
+        .esdt_roles(BET_TOKEN, vec!["ESDTRoleNFTCreate".to_string()]);
+    world.current_block().block_timestamp(0);
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.init();
+            sc.bet_nft_token()
+                .set_token_id(TokenIdentifier::from(BET_TOKEN.as_str()));
+            sc.add_accepted_token(EgldOrEsdtTokenIdentifier::egld(), BigUint::from(egld(1)), BigUint::from(egld(10_000)));
+            for (home, away) in [("Home", "Away"), ("Second Home", "Second Away")] {
+                sc.create_event(
+                    ManagedBuffer::from("Football"),
+                    ManagedBuffer::from("Liga 1"),
+                    ManagedBuffer::from(home),
+                    ManagedBuffer::from(away),
+                    CLOSE_TIMESTAMP,
+                );
+            }
+            // Tipul 1 din catalog e FullTime Result: HOME, DRAW, AWAY
+            sc.create_typed_market(
+                EVENT_ID,
+                1,
+                CLOSE_TIMESTAMP,
+                EgldOrEsdtTokenIdentifier::egld(),
+                OptionalValue::None,
+            );
+        });
+
+    world
+}
+
+fn place_bet(
+    world: &mut ScenarioWorld,
+    bettor: TestAddress,
+    payment: u128,
+    odds_hundredths: u64,
+    bet_type: BetType,
+) {
+    place_bet_with(world, bettor, payment, odds_hundredths, bet_type, TimeInForce::GoodTillClose, BetPersistence::Lapse);
+}
+
+fn place_bet_with(
+    world: &mut ScenarioWorld,
+    bettor: TestAddress,
+    payment: u128,
+    odds_hundredths: u64,
+    bet_type: BetType,
+    time_in_force: TimeInForce,
+    persistence: BetPersistence,
+) {
+    world
+        .tx()
+        .from(bettor)
+        .to(ROCKSTAKE)
+        .egld(balance(payment))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(odds_hundredths)),
+                bet_type,
+                OptionalValue::Some(time_in_force),
+                OptionalValue::Some(persistence),
+            );
+        });
+}
+
+/// Checks every matched bet against its counterparties: for the bets given,
+/// what the backers stand to collect equals the liability the layers locked
+/// plus the backers' stakes, and each side agrees on the matched stake.
+fn check_matches_balance(world: &mut ScenarioWorld, bet_ids: &[u64]) {
+    let bet_ids = bet_ids.to_vec();
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, move |sc| {
+            let mut backed_stake = BigUint::zero();
+            let mut backer_payout = BigUint::zero();
+            let mut laid_stake = BigUint::zero();
+            let mut layer_liability = BigUint::zero();
+
+            for bet_id in bet_ids {
+                let bet = sc.bet_by_id(bet_id).get();
+
+                let mut parts_total = BigUint::zero();
+                for part in bet.matched_parts.iter() {
+                    parts_total += &part.amount;
+                }
+                assert_eq!(parts_total, bet.matched_amount);
+                assert_eq!(bet.stake_amount, &bet.matched_amount + &bet.unmatched_amount);
+
+                match bet.bet_type {
+                    BetType::Back => {
+                        backed_stake += &bet.matched_amount;
+                        backer_payout += &bet.matched_amount + &bet.potential_profit;
+                    },
+                    BetType::Lay => {
+                        assert_eq!(bet.potential_profit, bet.matched_amount);
+                        let matched_liability = sc.calculate_matched_liability(&bet);
+                        assert_eq!(bet.liability, sc.calculate_required_collateral(&bet));
+                        laid_stake += &bet.matched_amount;
+                        layer_liability += matched_liability;
+                    },
+                }
+            }
+
+            assert_eq!(backed_stake, laid_stake);
+            assert_eq!(backer_payout, layer_liability + laid_stake);
+        });
+}
+
+fn check_locked_funds(world: &mut ScenarioWorld, bettor: TestAddress, expected: u128) {
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, move |sc| {
+            let locked = sc
+                .locked_funds(&bettor.to_managed_address(), &EgldOrEsdtTokenIdentifier::egld())
+                .get();
+            assert_eq!(locked, BigUint::from(expected));
+        });
+}
+
+fn check_available_balance(world: &mut ScenarioWorld, bettor: TestAddress, expected: u128) {
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, move |sc| {
+            let (available, _) = sc
+                .get_balance(bettor.to_managed_address(), EgldOrEsdtTokenIdentifier::egld())
+                .into_tuple();
+            assert_eq!(available, BigUint::from(expected));
+        });
+}
+
+fn check_bet(
+    world: &mut ScenarioWorld,
+    bet_id: u64,
+    matched: u128,
+    unmatched: u128,
+    liability: u128,
+    status: BetStatus,
+) {
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, move |sc| {
+            let bet = sc.bet_by_id(bet_id).get();
+            assert_eq!(bet.matched_amount, BigUint::from(matched));
+            assert_eq!(bet.unmatched_amount, BigUint::from(unmatched));
+            assert_eq!(bet.liability, BigUint::from(liability));
+            assert!(bet.status == status);
+        });
+}
+
+#[test]
+fn back_matches_resting_lay_at_same_odds() {
+    let mut world = setup();
+
+    // 10 EGLD of liability at 3.00 lays 5 EGLD of backer's stake
+    place_bet(&mut world, LAYER, egld(10), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(5), 300, BetType::Back);
+
+    check_bet(&mut world, 1, egld(5), 0, egld(10), BetStatus::Matched);
+    check_bet(&mut world, 2, egld(5), 0, 0, BetStatus::Matched);
+    check_matches_balance(&mut world, &[1, 2]);
+
+    check_locked_funds(&mut world, LAYER, egld(10));
+    check_locked_funds(&mut world, BACKER, egld(5));
+    world.check_account(ROCKSTAKE).balance(balance(egld(15)));
+}
+
+#[test]
+fn back_takes_better_priced_lay() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(12), 220, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(10), 200, BetType::Back);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let back = sc.bet_by_id(2).get();
+            assert_eq!(back.matched_parts.len(), 1);
+            assert_eq!(back.matched_parts.get(0).odds, BigUint::from(odds(220)));
+            assert_eq!(back.potential_profit, BigUint::from(egld(12)));
+        });
+    check_bet(&mut world, 1, egld(10), 0, egld(12), BetStatus::Matched);
+    check_matches_balance(&mut world, &[1, 2]);
+    world.check_account(ROCKSTAKE).balance(balance(egld(22)));
+}
+
+#[test]
+fn lay_takes_better_priced_back_and_frees_liability() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(10), 200, BetType::Back);
+    // 15 EGLD at 2.50 lays 10 EGLD, but matching at 2.00 only risks 10 EGLD
+    place_bet(&mut world, LAYER, egld(15), 250, BetType::Lay);
+
+    check_bet(&mut world, 1, egld(10), 0, 0, BetStatus::Matched);
+    check_bet(&mut world, 2, egld(10), 0, egld(10), BetStatus::Matched);
+    check_matches_balance(&mut world, &[1, 2]);
+
+    check_locked_funds(&mut world, LAYER, egld(10));
+    check_available_balance(&mut world, LAYER, egld(5));
+    world
+        .check_account(LAYER)
+        .balance(balance(STARTING_BALANCE - egld(15)));
+    world.check_account(ROCKSTAKE).balance(balance(egld(25)));
+}
+
+#[test]
+fn back_walks_lay_levels_best_price_first() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, SECOND_LAYER, egld(5), 350, BetType::Lay);
+    // Does not cross a Back at 3.00
+    place_bet(&mut world, SECOND_LAYER, egld(3), 250, BetType::Lay);
+
+    place_bet(&mut world, BACKER, egld(5), 300, BetType::Back);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let back = sc.bet_by_id(4).get();
+            assert_eq!(back.matched_parts.len(), 2);
+            let best = back.matched_parts.get(0);
+            assert_eq!(best.odds, BigUint::from(odds(350)));
+            assert_eq!(best.amount, BigUint::from(egld(2)));
+            let next = back.matched_parts.get(1);
+            assert_eq!(next.odds, BigUint::from(odds(300)));
+            assert_eq!(next.amount, BigUint::from(egld(2)));
+
+            let details = sc.get_matching_details(MARKET_ID, HOME);
+            assert_eq!(details.lay_levels.len(), 1);
+            assert_eq!(details.lay_levels.get(0).odds, BigUint::from(odds(250)));
+        });
+
+    check_bet(&mut world, 4, egld(4), egld(1), 0, BetStatus::PartiallyMatched);
+    check_bet(&mut world, 1, egld(2), 0, egld(4), BetStatus::Matched);
+    check_bet(&mut world, 2, egld(2), 0, egld(5), BetStatus::Matched);
+    check_matches_balance(&mut world, &[1, 2, 3, 4]);
+}
+
+#[test]
+fn resting_lay_fills_across_several_backs() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(9), 400, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(1), 400, BetType::Back);
+    place_bet(&mut world, SECOND_BACKER, egld(1), 350, BetType::Back);
+
+    check_bet(&mut world, 1, egld(2), egld(1), egld(9), BetStatus::PartiallyMatched);
+    check_matches_balance(&mut world, &[1, 2, 3]);
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(1);
+        });
+
+    check_bet(&mut world, 1, egld(2), 0, egld(6), BetStatus::Matched);
+    check_locked_funds(&mut world, LAYER, egld(6));
+    check_available_balance(&mut world, LAYER, egld(3));
+    world.check_account(LAYER).balance(balance(STARTING_BALANCE - egld(9)));
+    check_matches_balance(&mut world, &[1, 2, 3]);
+}
+
+#[test]
+fn resting_lay_liability_follows_odd_sized_fills() {
+    let mut world = setup();
+
+    // 37 EGLD of liability at 1.37 lays 100 EGLD; each fill rounds its liability down
+    place_bet(&mut world, LAYER, egld(37), 137, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(1) + 1, 137, BetType::Back);
+    place_bet(&mut world, SECOND_BACKER, egld(3) + 7, 137, BetType::Back);
+    place_bet(&mut world, BACKER, egld(5) + 3, 137, BetType::Back);
+
+    // Părțile matched rotunjite separat cer cu 2 unități mai puțin decât lock-ul inițial
+    check_bet(&mut world, 1, egld(9) + 11, egld(91) - 11, egld(37) - 2, BetStatus::PartiallyMatched);
+    check_locked_funds(&mut world, LAYER, egld(37) - 2);
+    check_available_balance(&mut world, LAYER, 2);
+    check_matches_balance(&mut world, &[1, 2, 3, 4]);
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(1);
+        });
+
+    let matched_liability = egld(9) * 37 / 100 + 3;
+    check_bet(&mut world, 1, egld(9) + 11, 0, matched_liability, BetStatus::Matched);
+    check_locked_funds(&mut world, LAYER, matched_liability);
+    check_available_balance(&mut world, LAYER, egld(37) - matched_liability);
+    check_matches_balance(&mut world, &[1, 2, 3, 4]);
+}
+
+#[test]
+fn same_price_bets_fill_first_in_first_out() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, SECOND_LAYER, egld(4), 300, BetType::Lay);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let position = sc.get_queue_position(2);
+            assert_eq!(position.bets_ahead, 1);
+            assert_eq!(position.stake_ahead, BigUint::from(egld(2)));
+            assert_eq!(position.level_total_stake, BigUint::from(egld(4)));
+        });
+
+    place_bet(&mut world, BACKER, egld(3), 300, BetType::Back);
+
+    check_bet(&mut world, 1, egld(2), 0, egld(4), BetStatus::Matched);
+    check_bet(&mut world, 2, egld(1), egld(1), egld(4), BetStatus::PartiallyMatched);
+    check_matches_balance(&mut world, &[1, 2, 3]);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let position = sc.get_queue_position(2);
+            assert_eq!(position.bets_ahead, 0);
+            assert_eq!(position.level_total_stake, BigUint::from(egld(1)));
+        });
+}
+
+#[test]
+fn price_levels_stay_sorted_within_a_bounded_walk() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(1), 200, BetType::Back);
+    for tick in (101..=150).rev() {
+        place_bet(&mut world, BACKER, egld(1), tick, BetType::Back);
+    }
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let levels = sc.get_matching_details(MARKET_ID, HOME).back_levels;
+            assert_eq!(levels.len(), 51);
+            assert_eq!(levels.get(0).odds, BigUint::from(odds(101)));
+            assert_eq!(levels.get(49).odds, BigUint::from(odds(150)));
+            assert_eq!(levels.get(50).odds, BigUint::from(odds(200)));
+        });
+
+    // 1.60 ar sta după cele 50 de niveluri mai bune
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(1)))
+        .returns(ExpectError(4, "Price too deep in the orderbook"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(160)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    // În spatele întregului orderbook se intră fără parcurgere
+    place_bet(&mut world, BACKER, egld(1), 250, BetType::Back);
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let levels = sc.get_matching_details(MARKET_ID, HOME).back_levels;
+            assert_eq!(levels.len(), 52);
+            assert_eq!(levels.get(51).odds, BigUint::from(odds(250)));
+        });
+}
+
+#[test]
+fn price_levels_are_kept_best_price_first() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+    place_bet(&mut world, BACKER, egld(1), 200, BetType::Back);
+    place_bet(&mut world, BACKER, egld(1), 250, BetType::Back);
+    place_bet(&mut world, LAYER, egld(1), 150, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(1), 180, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(1), 160, BetType::Lay);
+
+    // Scoaterea unui nivel din mijloc nu schimbă ordinea celorlalte
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(3);
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let details = sc.get_matching_details(MARKET_ID, HOME);
+            assert_eq!(details.back_levels.len(), 2);
+            assert_eq!(details.back_levels.get(0).odds, BigUint::from(odds(200)));
+            assert_eq!(details.back_levels.get(1).odds, BigUint::from(odds(300)));
+            assert_eq!(details.lay_levels.len(), 3);
+            assert_eq!(details.lay_levels.get(0).odds, BigUint::from(odds(180)));
+            assert_eq!(details.lay_levels.get(1).odds, BigUint::from(odds(160)));
+            assert_eq!(details.lay_levels.get(2).odds, BigUint::from(odds(150)));
+        });
+}
+
+#[test]
+fn reducing_a_bet_keeps_its_place_in_the_queue() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(3), 250, BetType::Back);
+    place_bet(&mut world, BACKER, egld(2), 250, BetType::Back);
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.update_bet(1, OptionalValue::None, OptionalValue::Some(BigUint::from(egld(1))));
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.get_queue_position(1).bets_ahead, 0);
+            let position = sc.get_queue_position(2);
+            assert_eq!(position.bets_ahead, 1);
+            assert_eq!(position.stake_ahead, BigUint::from(egld(1)));
+            assert_eq!(position.level_total_stake, BigUint::from(egld(3)));
+        });
+}
+
+#[test]
+fn orderbook_keeps_a_queue_per_price_level() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(2), 200, BetType::Back);
+    place_bet(&mut world, BACKER, egld(1), 200, BetType::Back);
+    place_bet(&mut world, BACKER, egld(1), 250, BetType::Back);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let odds_index = sc.selection_level_odds(MARKET_ID, HOME, BetType::Back);
+            let level_odds: Vec<u64> = odds_index
+                .iter()
+                .map(|node| node.into_value().to_u64().unwrap())
+                .collect();
+            assert_eq!(level_odds, vec![odds(200), odds(250)]);
+
+            let queue = sc.level_queue(MARKET_ID, HOME, BetType::Back, &BigUint::from(odds(200)));
+            let nonces: Vec<u64> = queue.iter().map(|node| node.into_value()).collect();
+            assert_eq!(nonces, vec![1, 2]);
+            assert_eq!(
+                sc.level_total_stake(MARKET_ID, HOME, BetType::Back, &BigUint::from(odds(200))).get(),
+                BigUint::from(egld(3))
+            );
+        });
+
+    // Un Lay la 2.00 consumă doar primul pariu din coada nivelului
+    place_bet(&mut world, LAYER, egld(2), 200, BetType::Lay);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let queue = sc.level_queue(MARKET_ID, HOME, BetType::Back, &BigUint::from(odds(200)));
+            let nonces: Vec<u64> = queue.iter().map(|node| node.into_value()).collect();
+            assert_eq!(nonces, vec![2]);
+            assert!(sc.bet_queue_node(1).is_empty());
+            assert_eq!(
+                sc.level_total_stake(MARKET_ID, HOME, BetType::Back, &BigUint::from(odds(200))).get(),
+                BigUint::from(egld(1))
+            );
+            assert_eq!(sc.market_total_matched(MARKET_ID).get(), BigUint::from(egld(2)));
+        });
+
+    // Ultimul pariu anulat scoate nivelul din index
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(2);
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let odds_index = sc.selection_level_odds(MARKET_ID, HOME, BetType::Back);
+            assert_eq!(odds_index.len(), 1);
+            assert_eq!(odds_index.front().unwrap().into_value(), BigUint::from(odds(250)));
+            assert!(sc.level_node_id(MARKET_ID, HOME, BetType::Back, &BigUint::from(odds(200))).is_empty());
+            assert!(sc.level_total_stake(MARKET_ID, HOME, BetType::Back, &BigUint::from(odds(200))).is_empty());
+            assert_eq!(sc.selection_back_liquidity(MARKET_ID, HOME).get(), BigUint::from(egld(1)));
+        });
+}
+
+#[test]
+fn lay_stake_rounds_down_and_refunds_dust() {
+    let mut world = setup();
+
+    // 2 EGLD + 1 of liability at 3.00 covers 1 EGLD of stake, the extra unit goes to the balance
+    place_bet(&mut world, LAYER, egld(2) + 1, 300, BetType::Lay);
+
+    check_bet(&mut world, 1, 0, egld(1), egld(2), BetStatus::Unmatched);
+    check_locked_funds(&mut world, LAYER, egld(2));
+    check_available_balance(&mut world, LAYER, 1);
+    world.check_account(ROCKSTAKE).balance(balance(egld(2) + 1));
+}
+
+#[test]
+fn immediate_or_cancel_refunds_unmatched_remainder() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(6), 300, BetType::Lay);
+    place_bet_with(
+        &mut world,
+        BACKER,
+        egld(5),
+        300,
+        BetType::Back,
+        TimeInForce::ImmediateOrCancel,
+        BetPersistence::Lapse,
+    );
+
+    check_bet(&mut world, 2, egld(3), 0, 0, BetStatus::Matched);
+    check_locked_funds(&mut world, BACKER, egld(3));
+    check_available_balance(&mut world, BACKER, egld(2));
+    world.check_account(BACKER).balance(balance(STARTING_BALANCE - egld(5)));
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let details = sc.get_matching_details(MARKET_ID, HOME);
+            assert_eq!(details.back_levels.len(), 0);
+            assert_eq!(details.lay_levels.len(), 0);
+        });
+    check_matches_balance(&mut world, &[1, 2]);
+}
+
+#[test]
+fn fill_or_kill_reverts_unless_fully_matched() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(6), 300, BetType::Lay);
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(5)))
+        .returns(ExpectError(4, "Bet could not be fully matched"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::Some(TimeInForce::FillOrKill),
+                OptionalValue::None,
+            );
+        });
+
+    check_bet(&mut world, 1, 0, egld(3), egld(6), BetStatus::Unmatched);
+    world.check_account(BACKER).balance(balance(STARTING_BALANCE));
+
+    place_bet_with(
+        &mut world,
+        BACKER,
+        egld(3),
+        300,
+        BetType::Back,
+        TimeInForce::FillOrKill,
+        BetPersistence::Lapse,
+    );
+    check_bet(&mut world, 2, egld(3), 0, 0, BetStatus::Matched);
+}
+
+#[test]
+fn keep_in_play_orders_survive_market_going_in_play() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut selection_values = ManagedVec::new();
+            selection_values.push(1u64);
+            selection_values.push(2u64);
+            sc.create_market(
+                SECOND_EVENT_ID,
+                ManagedBuffer::from("FullTime Result"),
+                selection_values,
+                CLOSE_TIMESTAMP,
+                EgldOrEsdtTokenIdentifier::egld(),
+                OptionalValue::Some(true),
+            );
+        });
+
+    for (bettor, persistence) in [
+        (BACKER, BetPersistence::KeepInPlay),
+        (SECOND_BACKER, BetPersistence::Lapse),
+    ] {
+        world
+            .tx()
+            .from(bettor)
+            .to(ROCKSTAKE)
+            .egld(balance(egld(2)))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.place_bet(
+                    ManagedBuffer::from("cid"),
+                    2,
+                    HOME,
+                    BigUint::from(odds(250)),
+                    BetType::Back,
+                    OptionalValue::None,
+                    OptionalValue::Some(persistence),
+                );
+            });
+    }
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(2);
+        });
+
+    check_bet(&mut world, 1, 0, egld(2), 0, BetStatus::Unmatched);
+    check_bet(&mut world, 2, 0, 0, 0, BetStatus::Canceled);
+    check_available_balance(&mut world, SECOND_BACKER, egld(2));
+
+    // In-play the remaining order still matches
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(3)))
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert!(sc.markets(2).get().market_status == MarketStatus::InPlay);
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                2,
+                HOME,
+                BigUint::from(odds(250)),
+                BetType::Lay,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+    check_bet(&mut world, 1, egld(2), 0, 0, BetStatus::Matched);
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Only owner can close an in-play market"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(2);
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(2);
+            assert!(sc.markets(2).get().market_status == MarketStatus::Closed);
+        });
+}
+
+fn set_self_trade_prevention(world: &mut ScenarioWorld, mode: SelfTradePrevention) {
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_self_trade_prevention(mode);
+        });
+}
+
+#[test]
+fn self_match_cancels_resting_bet_by_default() {
+    let mut world = setup();
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert!(sc.get_self_trade_prevention() == SelfTradePrevention::CancelResting);
+        });
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, SECOND_LAYER, egld(4), 300, BetType::Lay);
+    // LAYER backs against their own resting Lay
+    place_bet(&mut world, LAYER, egld(3), 300, BetType::Back);
+
+    check_bet(&mut world, 1, 0, 0, 0, BetStatus::Canceled);
+    check_bet(&mut world, 2, egld(2), 0, egld(4), BetStatus::Matched);
+    check_bet(&mut world, 3, egld(2), egld(1), 0, BetStatus::PartiallyMatched);
+    check_locked_funds(&mut world, LAYER, egld(3));
+    check_available_balance(&mut world, LAYER, egld(4));
+    world.check_account(LAYER).balance(balance(STARTING_BALANCE - egld(7)));
+    check_matches_balance(&mut world, &[2, 3]);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.total_matched_amount(MARKET_ID, HOME).get(), BigUint::from(egld(2)));
+            let details = sc.get_matching_details(MARKET_ID, HOME);
+            assert_eq!(details.lay_levels.len(), 0);
+            assert_eq!(details.back_levels.len(), 1);
+        });
+}
+
+#[test]
+fn self_match_skip_leaves_own_bet_in_queue() {
+    let mut world = setup();
+    set_self_trade_prevention(&mut world, SelfTradePrevention::Skip);
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, SECOND_LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(2), 300, BetType::Back);
+
+    check_bet(&mut world, 1, 0, egld(2), egld(4), BetStatus::Unmatched);
+    check_bet(&mut world, 2, egld(2), 0, egld(4), BetStatus::Matched);
+    check_bet(&mut world, 3, egld(2), 0, 0, BetStatus::Matched);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let details = sc.get_matching_details(MARKET_ID, HOME);
+            assert_eq!(details.lay_levels.len(), 1);
+            assert_eq!(details.lay_levels.get(0).total_stake, BigUint::from(egld(2)));
+        });
+}
+
+#[test]
+fn self_match_cancel_incoming_refunds_remainder() {
+    let mut world = setup();
+    set_self_trade_prevention(&mut world, SelfTradePrevention::CancelIncoming);
+
+    place_bet(&mut world, SECOND_LAYER, egld(2), 300, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(3), 300, BetType::Back);
+
+    check_bet(&mut world, 1, egld(1), 0, egld(2), BetStatus::Matched);
+    check_bet(&mut world, 2, 0, egld(2), egld(4), BetStatus::Unmatched);
+    check_bet(&mut world, 3, egld(1), 0, 0, BetStatus::Matched);
+    check_locked_funds(&mut world, LAYER, egld(5));
+    check_available_balance(&mut world, LAYER, egld(2));
+    world.check_account(LAYER).balance(balance(STARTING_BALANCE - egld(7)));
+}
+
+#[test]
+fn place_bets_quotes_several_selections_in_one_payment() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(9) + 1))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut orders = MultiValueEncoded::new();
+            orders.push((MARKET_ID, HOME, BigUint::from(odds(300)), BetType::Back, BigUint::from(egld(3))).into());
+            orders.push((MARKET_ID, 2, BigUint::from(odds(400)), BetType::Back, BigUint::from(egld(2))).into());
+            orders.push((MARKET_ID, 3, BigUint::from(odds(300)), BetType::Lay, BigUint::from(egld(4) + 1)).into());
+
+            let results = sc.place_bets(ManagedBuffer::from("cid"), false, orders);
+            assert_eq!(results.len(), 3);
+
+            let home = results.get(0);
+            assert_eq!(home.bet_id, 2);
+            assert_eq!(home.nft_nonce, 0);
+            assert_eq!(home.matched_amount, BigUint::from(egld(2)));
+            assert_eq!(home.unmatched_amount, BigUint::from(egld(1)));
+            assert!(home.status == BetStatus::PartiallyMatched);
+
+            let lay = results.get(2);
+            assert_eq!(lay.bet_id, 4);
+            assert_eq!(lay.unmatched_amount, BigUint::from(egld(2)));
+            assert_eq!(lay.refunded_amount, BigUint::from(1u32));
+        });
+
+    check_matches_balance(&mut world, &[1, 2]);
+    check_locked_funds(&mut world, BACKER, egld(9));
+    check_available_balance(&mut world, BACKER, 1);
+    world.check_account(BACKER).balance(balance(STARTING_BALANCE - egld(9) - 1));
+    world.check_account(ROCKSTAKE).balance(balance(egld(13) + 1));
+}
+
+#[test]
+fn place_bets_rejects_payment_not_matching_orders() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(5)))
+        .returns(ExpectError(4, "Payment does not match bet amounts"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut orders = MultiValueEncoded::new();
+            orders.push((MARKET_ID, HOME, BigUint::from(odds(300)), BetType::Back, BigUint::from(egld(3))).into());
+            orders.push((MARKET_ID, 2, BigUint::from(odds(300)), BetType::Back, BigUint::from(egld(3))).into());
+            sc.place_bets(ManagedBuffer::from("cid"), true, orders);
+        });
+}
+
+#[test]
+fn cancel_all_bets_pulls_open_quotes() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(6), 400, BetType::Lay);
+    place_bet(&mut world, LAYER, egld(8), 500, BetType::Lay);
+    // Both Backs take the best priced Lay, which leaves the open-orders index once filled
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+    check_bet(&mut world, 3, egld(2), 0, egld(8), BetStatus::Matched);
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.user_open_bets(&LAYER.to_managed_address()).len(), 2);
+            let canceled = sc.cancel_all_bets(OptionalValue::Some(MARKET_ID), OptionalValue::Some(HOME));
+            assert_eq!(canceled, 2);
+            assert!(sc.user_open_bets(&LAYER.to_managed_address()).is_empty());
+        });
+
+    check_bet(&mut world, 1, 0, 0, 0, BetStatus::Canceled);
+    check_bet(&mut world, 2, 0, 0, 0, BetStatus::Canceled);
+    check_locked_funds(&mut world, LAYER, egld(8));
+    check_available_balance(&mut world, LAYER, egld(10));
+    world.check_account(LAYER).balance(balance(STARTING_BALANCE - egld(18)));
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Bet cannot be cancelled"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_nonces = MultiValueEncoded::new();
+            bet_nonces.push(4u64);
+            sc.cancel_bets(bet_nonces);
+        });
+}
+
+#[test]
+fn cancel_bets_refunds_listed_bets() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+    place_bet(&mut world, BACKER, egld(3), 350, BetType::Back);
+    place_bet(&mut world, BACKER, egld(4), 400, BetType::Back);
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_nonces = MultiValueEncoded::new();
+            bet_nonces.push(1u64);
+            bet_nonces.push(3u64);
+            sc.cancel_bets(bet_nonces);
+        });
+
+    check_bet(&mut world, 1, 0, 0, 0, BetStatus::Canceled);
+    check_bet(&mut world, 2, 0, egld(3), 0, BetStatus::Unmatched);
+    check_bet(&mut world, 3, 0, 0, 0, BetStatus::Canceled);
+    check_locked_funds(&mut world, BACKER, egld(3));
+    check_available_balance(&mut world, BACKER, egld(6));
+    world.check_account(BACKER).balance(balance(STARTING_BALANCE - egld(9)));
+}
+
+#[test]
+fn user_bets_are_paged_by_status() {
+    let mut world = setup();
+
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+    place_bet(&mut world, BACKER, egld(2), 350, BetType::Back);
+    place_bet(&mut world, LAYER, egld(2), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(3), 400, BetType::Back);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let backer = BACKER.to_managed_address();
+            assert_eq!(sc.get_user_bet_count(backer.clone()), 3);
+
+            let all = sc.get_user_bets(backer.clone(), BetStatusFilter::All, 1, 10);
+            assert_eq!(all.len(), 2);
+            assert_eq!(all.get(0).nonce, 2);
+            assert_eq!(all.get(1).nonce, 4);
+
+            let open = sc.get_user_bets(backer.clone(), BetStatusFilter::Open, 0, 1);
+            assert_eq!(open.len(), 1);
+            assert_eq!(open.get(0).nonce, 2);
+
+            let matched = sc.get_user_bets(backer, BetStatusFilter::Matched, 0, 10);
+            assert_eq!(matched.len(), 1);
+            assert_eq!(matched.get(0).nonce, 1);
+            assert_eq!(matched.get(0).matched, BigUint::from(egld(1)));
+        });
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_nonces = MultiValueEncoded::new();
+            bet_nonces.push(4u64);
+            sc.cancel_bets(bet_nonces);
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let backer = BACKER.to_managed_address();
+            let open = sc.get_user_bets(backer.clone(), BetStatusFilter::Open, 0, 10);
+            assert_eq!(open.len(), 1);
+            assert_eq!(open.get(0).nonce, 2);
+
+            let settled = sc.get_user_bets(backer.clone(), BetStatusFilter::Settled, 0, 10);
+            assert_eq!(settled.len(), 1);
+            assert_eq!(settled.get(0).nonce, 4);
+            assert!(settled.get(0).status == BetStatus::Canceled);
+
+            assert!(sc.get_user_bets(backer, BetStatusFilter::Settled, 1, 10).is_empty());
+        });
+}
+
+#[test]
+fn odds_must_sit_on_the_ladder() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(1)))
+        .returns(ExpectError(4, "Odds not on ladder"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(201)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.get_next_tick_up(odds(201)), odds(202));
+            assert_eq!(sc.get_next_tick_down(odds(201)), odds(200));
+            assert_eq!(sc.get_next_tick_up(odds(200)), odds(202));
+            assert_eq!(sc.get_next_tick_down(odds(305)), odds(300));
+            assert_eq!(sc.get_next_tick_up(odds(100)), odds(101));
+            assert_eq!(sc.get_next_tick_down(odds(200_000)), odds(100_000));
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bands = MultiValueEncoded::new();
+            bands.push((odds(200), odds(5)).into());
+            bands.push((odds(1000), odds(50)).into());
+            sc.set_odds_ladder(odds(150), bands);
+            assert_eq!(sc.get_next_tick_up(odds(150)), odds(155));
+            assert_eq!(sc.get_next_tick_down(odds(1000)), odds(950));
+        });
+
+    place_bet(&mut world, BACKER, egld(1), 250, BetType::Back);
+    check_bet(&mut world, 1, 0, egld(1), 0, BetStatus::Unmatched);
+}
+
+fn place_raw_bet(world: &mut ScenarioWorld, bettor: TestAddress, payment: u128, raw_odds: u64, bet_type: BetType) {
+    world
+        .tx()
+        .from(bettor)
+        .to(ROCKSTAKE)
+        .egld(balance(payment))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(raw_odds),
+                bet_type,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+}
+
+#[test]
+fn upgrade_continues_bet_ids_after_the_last_ticket() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+
+    // Contractele vechi nu țineau last_bet_id, pariurile erau cheiate după nonce-ul biletului
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.last_bet_id().clear();
+            sc.upgrade();
+            assert_eq!(sc.last_bet_id().get(), 2);
+        });
+
+    place_bet(&mut world, SECOND_LAYER, egld(2), 300, BetType::Lay);
+    check_bet(&mut world, 1, egld(1), egld(1), egld(4), BetStatus::PartiallyMatched);
+    check_bet(&mut world, 2, egld(1), 0, 0, BetStatus::Matched);
+    check_bet(&mut world, 3, 0, egld(1), egld(2), BetStatus::Unmatched);
+}
+
+#[test]
+fn odds_scale_change_rescales_stored_prices() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+    place_bet(&mut world, SECOND_LAYER, egld(6), 400, BetType::Lay);
+
+    // Trecem de la zecimi de miime la milionimi: fiecare cotă se înmulțește cu 100
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_odds_scale(1_000_000);
+            assert!(!sc.migrate_odds(2));
+            let progress = sc.get_odds_migration_progress();
+            assert_eq!(progress.bets_migrated, 2);
+            assert_eq!(progress.target_scale, 1_000_000);
+        });
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Odds migration in progress"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(2);
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert!(sc.migrate_odds(10));
+            assert_eq!(sc.get_odds_scale(), 1_000_000);
+            assert_eq!(sc.get_next_tick_up(2_000_000), 2_020_000);
+
+            let laid = sc.bet_by_id(1).get();
+            assert_eq!(laid.odd, BigUint::from(3_000_000u64));
+            assert_eq!(laid.matched_parts.get(0).odds, BigUint::from(3_000_000u64));
+
+            let details = sc.get_matching_details(MARKET_ID, HOME);
+            assert_eq!(details.lay_levels.len(), 2);
+            assert_eq!(details.lay_levels.get(0).odds, BigUint::from(4_000_000u64));
+            assert_eq!(details.lay_levels.get(1).odds, BigUint::from(3_000_000u64));
+            assert_eq!(details.lay_levels.get(1).total_stake, BigUint::from(egld(1)));
+        });
+
+    place_raw_bet(&mut world, SECOND_BACKER, egld(3), 3_000_000, BetType::Back);
+    check_bet(&mut world, 4, egld(3), 0, 0, BetStatus::Matched);
+    check_bet(&mut world, 3, egld(2), 0, egld(6), BetStatus::Matched);
+    check_matches_balance(&mut world, &[1, 2, 3, 4]);
+}
+
+const LEGACY_EVENT_ID: u64 = 7;
+
+fn legacy_selections<M: ManagedTypeApi>(count: u64) -> ManagedVec<M, LegacySelection<M>> {
+    let mut selections = ManagedVec::new();
+    for id in 1..=count {
+        selections.push(LegacySelection {
+            id,
+            value: id,
+            priority_queue: LegacyTracker {
+                back_levels: ManagedVec::new(),
+                lay_levels: ManagedVec::new(),
+                back_liquidity: BigUint::zero(),
+                lay_liquidity: BigUint::zero(),
+                matched_count: 0,
+                unmatched_count: 0,
+                partially_matched_count: 0,
+                win_count: 0,
+                lost_count: 0,
+                canceled_count: 0,
+            },
+        });
+    }
+    selections
+}
+
+/// Un pariu încă nepotrivit pe HOME, cu cota în sutimi ca în prima versiune
+fn legacy_bet<M: ManagedTypeApi>(bettor: TestAddress, bet_type: BetType, odds_h: u64, stake: u128, status: BetStatus) -> LegacyBet<M> {
+    let stake = BigUint::from(egld(stake));
+    let profit = &stake * (odds_h - 100) / 100u64;
+    let (liability, potential_profit) = match bet_type {
+        BetType::Back => (BigUint::zero(), profit),
+        BetType::Lay => (profit, stake.clone()),
+    };
+    LegacyBet {
+        bettor: bettor.to_managed_address(),
+        event: MARKET_ID,
+        selection: legacy_selections(HOME).get(0).clone(),
+        stake_amount: stake.clone(),
+        liability,
+        matched_amount: BigUint::zero(),
+        matched_parts: ManagedVec::new(),
+        unmatched_amount: stake,
+        potential_profit,
+        odd: BigUint::from(odds_h),
+        bet_type,
+        status,
+        payment_token: EgldOrEsdtTokenIdentifier::egld(),
+        payment_nonce: 0,
+        nft_nonce: 0,
+        created_at: 0,
+    }
+}
+
+fn legacy_level<M: ManagedTypeApi>(odds_h: u64, bet_ids: &[u64]) -> LegacyPriceLevel<M> {
+    let mut bet_nonces = ManagedVec::new();
+    for bet_id in bet_ids {
+        bet_nonces.push(*bet_id);
+    }
+    LegacyPriceLevel { odds: BigUint::from(odds_h), total_stake: BigUint::zero(), bet_nonces }
+}
+
+/// Un contract din prima versiune: fără init nou, cu înregistrările în layout-ul vechi
+fn setup_legacy() -> ScenarioWorld {
+    let mut world = ScenarioWorld::new();
+    world.register_contract(CODE_PATH, rockstake::ContractBuilder);
+
+    world.account(OWNER).nonce(1);
+    for bettor in [BACKER, SECOND_BACKER, LAYER, SECOND_LAYER] {
+        world.account(bettor).nonce(1).balance(balance(STARTING_BALANCE));
+    }
+    world
+        .account(ROCKSTAKE)
+        .nonce(1)
+        .code(CODE_PATH)
+        .owner(OWNER)
+        .balance(balance(egld(5)))
+        .esdt_nft_last_nonce(BET_TOKEN, 5)
+        .esdt_roles(BET_TOKEN, vec!["ESDTRoleNFTCreate".to_string()]);
+    world.current_block().block_timestamp(0);
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.bet_nft_token()
+                .set_token_id(TokenIdentifier::from(BET_TOKEN.as_str()));
+            sc.market_counter().set(2);
+            for (market_id, market_status) in [(1u64, LegacyMarketStatus::Open), (2, LegacyMarketStatus::Settled)] {
+                sc.legacy_markets(market_id).set(LegacyMarket {
+                    market_id,
+                    event_id: LEGACY_EVENT_ID,
+                    description: ManagedBuffer::from("FullTime Result"),
+                    selections: legacy_selections(3),
+                    close_timestamp: CLOSE_TIMESTAMP,
+                    market_status,
+                    total_matched_amount: BigUint::from(egld(2)),
+                    liquidity: BigUint::zero(),
+                    created_at: 0,
+                });
+                sc.markets_by_event(LEGACY_EVENT_ID).update(|markets| markets.push(market_id));
+            }
+
+            // Nivelurile Back au rămas amestecate de swap-remove, iar pariul 4 e anulat.
+            // Contractul ține doar mizele: 5 EGLD, din care layerii au plătit câte 1 EGLD
+            let bets = [
+                legacy_bet(BACKER, BetType::Back, 250, 1, BetStatus::Unmatched),
+                legacy_bet(SECOND_BACKER, BetType::Back, 200, 2, BetStatus::Unmatched),
+                legacy_bet(LAYER, BetType::Lay, 180, 1, BetStatus::Unmatched),
+                legacy_bet(BACKER, BetType::Back, 250, 1, BetStatus::Canceled),
+                legacy_bet(SECOND_LAYER, BetType::Lay, 300, 1, BetStatus::Unmatched),
+            ];
+            for (index, bet) in bets.into_iter().enumerate() {
+                let bet_id = index as u64 + 1;
+                sc.legacy_bet_by_id(bet_id).set(bet);
+                sc.market_bet_ids(MARKET_ID).insert(bet_id);
+            }
+            let mut back_levels = ManagedVec::new();
+            back_levels.push(legacy_level(250, &[4, 1]));
+            back_levels.push(legacy_level(200, &[2]));
+            sc.legacy_selection_back_levels(MARKET_ID, HOME).set(back_levels);
+            let mut lay_levels = ManagedVec::new();
+            lay_levels.push(legacy_level(180, &[3]));
+            lay_levels.push(legacy_level(300, &[5]));
+            sc.legacy_selection_lay_levels(MARKET_ID, HOME).set(lay_levels);
+        });
+
+    world
+}
+
+/// Piața 2 nu are pariuri din care să-i aflăm tokenul, așa că îl dă owner-ul
+fn migrate_legacy_state(world: &mut ScenarioWorld) {
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.upgrade();
+            sc.set_legacy_market_token(2, EgldOrEsdtTokenIdentifier::egld());
+            assert!(!sc.migrate_legacy_state(1));
+            assert!(sc.migrate_legacy_state(10));
+        });
+}
+
+#[test]
+fn upgrade_converts_first_version_markets() {
+    let mut world = setup_legacy();
+    migrate_legacy_state(&mut world);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let market = sc.markets(MARKET_ID).get();
+            assert_eq!(market.event_id, LEGACY_EVENT_ID);
+            assert_eq!(market.selections.len(), 3);
+            assert_eq!(market.selections.get(2).id, 3);
+            assert!(market.token == EgldOrEsdtTokenIdentifier::egld());
+            assert!(market.market_status == MarketStatus::Open);
+            assert!(!market.in_play);
+            assert_eq!(sc.market_total_matched(MARKET_ID).get(), BigUint::from(egld(2)));
+
+            assert!(sc.markets(2).get().market_status == MarketStatus::Settled);
+        });
+}
+
+#[test]
+fn closed_first_version_market_settles_by_its_position() {
+    let mut world = setup_legacy();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.legacy_markets(2).update(|market| market.market_status = LegacyMarketStatus::Closed);
+        });
+    migrate_legacy_state(&mut world);
+
+    // A doua piață a evenimentului era Total Goals O/U 2.5
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.market_type_of(MARKET_ID).get(), 1);
+            assert_eq!(sc.market_type_of(2).get(), 2);
+
+            sc.set_market_result(LEGACY_EVENT_ID, 2, 2, 1);
+            let (winners, status) = sc.get_market_settlement_details(2);
+            assert!(status == MarketStatus::Settled);
+            assert_eq!(winners.get(0).selection_id, 1);
+        });
+}
+
+#[test]
+fn upgrade_registers_first_version_events() {
+    let mut world = setup_legacy();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.upgrade();
+            sc.set_legacy_market_token(2, EgldOrEsdtTokenIdentifier::egld());
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Legacy migration in progress"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.create_event(
+                ManagedBuffer::from("Football"),
+                ManagedBuffer::from("Liga 1"),
+                ManagedBuffer::from("Home"),
+                ManagedBuffer::from("Away"),
+                CLOSE_TIMESTAMP,
+            );
+        });
+
+    // Evenimentul vechi ia statusul pieței celei mai puțin avansate
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert!(sc.migrate_legacy_state(10));
+            let event = sc.get_event(LEGACY_EVENT_ID);
+            assert!(event.status == EventStatus::Scheduled);
+            assert_eq!(event.start_timestamp, CLOSE_TIMESTAMP);
+            assert_eq!(sc.event_counter().get(), LEGACY_EVENT_ID);
+
+            sc.add_accepted_token(EgldOrEsdtTokenIdentifier::egld(), BigUint::from(egld(1)), BigUint::from(egld(10_000)));
+            let event_id = sc.create_event(
+                ManagedBuffer::from("Football"),
+                ManagedBuffer::from("Liga 1"),
+                ManagedBuffer::from("Home"),
+                ManagedBuffer::from("Away"),
+                CLOSE_TIMESTAMP,
+            );
+            assert_eq!(event_id, LEGACY_EVENT_ID + 1);
+            assert!(sc.markets_by_event(event_id).get().is_empty());
+
+            let market_id = sc.create_typed_market(
+                LEGACY_EVENT_ID,
+                1,
+                CLOSE_TIMESTAMP,
+                EgldOrEsdtTokenIdentifier::egld(),
+                OptionalValue::None,
+            );
+            assert_eq!(market_id, 3);
+            assert_eq!(sc.markets_by_event(LEGACY_EVENT_ID).get().len(), 3);
+        });
+}
+
+#[test]
+fn legacy_market_without_bets_needs_its_token() {
+    let mut world = setup_legacy();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.upgrade();
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Legacy market token not set"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.migrate_legacy_state(10);
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_legacy_market_token(2, EgldOrEsdtTokenIdentifier::esdt(STABLE_TOKEN.to_token_identifier()));
+            assert!(sc.migrate_legacy_state(10));
+            assert!(sc.markets(2).get().token == EgldOrEsdtTokenIdentifier::esdt(STABLE_TOKEN.to_token_identifier()));
+            assert!(sc.legacy_market_token(2).is_empty());
+        });
+}
+
+#[test]
+fn upgrade_rebuilds_first_version_price_levels() {
+    let mut world = setup_legacy();
+    migrate_legacy_state(&mut world);
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let back_odds: Vec<BigUint<DebugApi>> = sc
+                .selection_level_odds(MARKET_ID, HOME, BetType::Back)
+                .iter()
+                .map(|node| node.into_value())
+                .collect();
+            assert_eq!(back_odds, vec![BigUint::from(odds(200)), BigUint::from(odds(250))]);
+
+            let worst = BigUint::from(odds(250));
+            let queue: Vec<u64> = sc
+                .level_queue(MARKET_ID, HOME, BetType::Back, &worst)
+                .iter()
+                .map(|node| node.into_value())
+                .collect();
+            assert_eq!(queue, vec![1]);
+            assert_eq!(sc.level_total_stake(MARKET_ID, HOME, BetType::Back, &worst).get(), BigUint::from(egld(1)));
+            assert!(sc.bet_queue_node(4).is_empty());
+
+            assert_eq!(sc.selection_back_liquidity(MARKET_ID, HOME).get(), BigUint::from(egld(3)));
+            // Lay-ul la 3.00 a rămas doar cu cât acoperă miza plătită
+            assert_eq!(sc.selection_lay_liquidity(MARKET_ID, HOME).get(), BigUint::from(egld(3) / 2));
+            assert!(sc.user_open_bets(&SECOND_BACKER.to_managed_address()).contains(&2));
+            assert!(sc.legacy_selection_back_levels(MARKET_ID, HOME).is_empty());
+            assert!(sc.legacy_selection_tracker(MARKET_ID, HOME).is_empty());
+        });
+}
+
+#[test]
+fn upgrade_migrates_first_version_bets() {
+    let mut world = setup_legacy();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.upgrade();
+            sc.set_legacy_market_token(2, EgldOrEsdtTokenIdentifier::egld());
+            assert!(!sc.migrate_legacy_state(3));
+        });
+    world
+        .tx()
+        .from(SECOND_LAYER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(2)))
+        .returns(ExpectError(4, "Legacy migration in progress"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(200)),
+                BetType::Lay,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert!(sc.migrate_legacy_state(10));
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let bet = sc.bet_by_id(1).get();
+            assert_eq!(bet.id, 1);
+            assert_eq!(bet.odd, BigUint::from(odds(250)));
+            assert!(bet.persistence == BetPersistence::Lapse);
+            assert!(sc.bet_settled(4).get());
+            assert_eq!(sc.user_bets(&BACKER.to_managed_address()).len(), 2);
+            assert_eq!(sc.user_settled_bets(&BACKER.to_managed_address()).len(), 1);
+            assert!(sc.legacy_locked_funds(&BACKER.to_managed_address()).is_empty());
+        });
+    check_bet(&mut world, 3, 0, egld(1), egld(1) * 8 / 10, BetStatus::Unmatched);
+    check_locked_funds(&mut world, BACKER, egld(1));
+    check_locked_funds(&mut world, LAYER, egld(1) * 8 / 10);
+    check_exposure(&mut world, LAYER, egld(1) * 8 / 10, egld(1) * 8 / 10);
+
+    // Layerii au plătit doar miza: sub 2.00 restul merge în sold, peste 2.00
+    // partea nepotrivită se reduce la cât acoperă miza
+    check_available_balance(&mut world, LAYER, egld(1) * 2 / 10);
+    check_bet(&mut world, 5, 0, egld(1) / 2, egld(1), BetStatus::Unmatched);
+    check_locked_funds(&mut world, SECOND_LAYER, egld(1));
+    check_solvency(&mut world, egld(5), egld(5));
+
+    // Prima versiune nu avea limite de miză: tokenul nu primește pariuri noi până nu le setează owner-ul
+    world
+        .tx()
+        .from(SECOND_LAYER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(2)))
+        .returns(ExpectError(4, "Stake limits not set for token"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(200)),
+                BetType::Lay,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_token_stake_limits(EgldOrEsdtTokenIdentifier::egld(), BigUint::from(egld(1)), BigUint::from(egld(100)));
+        });
+
+    // Un pariu nou primește id-ul de după ultimul bilet și se potrivește cu unul vechi
+    place_bet(&mut world, SECOND_LAYER, egld(2), 200, BetType::Lay);
+    check_bet(&mut world, 6, egld(2), 0, egld(2), BetStatus::Matched);
+    check_bet(&mut world, 2, egld(2), 0, 0, BetStatus::Matched);
+    check_matches_balance(&mut world, &[2, 6]);
+    check_solvency(&mut world, egld(7), egld(7));
+}
+
+#[test]
+fn odds_convert_between_formats() {
+    let mut world = setup();
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let odds_on = sc.get_odds_formats(odds(250));
+            assert_eq!((odds_on.fractional_numerator, odds_on.fractional_denominator), (3, 2));
+            assert_eq!(odds_on.american, 150);
+            assert_eq!(odds_on.implied_probability_bps, 4_000);
+
+            let favourite = sc.get_odds_formats(odds(150));
+            assert_eq!((favourite.fractional_numerator, favourite.fractional_denominator), (1, 2));
+            assert_eq!(favourite.american, -200);
+            assert_eq!(favourite.implied_probability_bps, 6_666);
+
+            // 11/8 = 2.375 nu e pe scară: Back urcă la 2.38, Lay coboară la 2.36
+            assert_eq!(sc.normalize_odds(OddsInput::Fractional(11, 8), BetType::Back), odds(238));
+            assert_eq!(sc.normalize_odds(OddsInput::Fractional(11, 8), BetType::Lay), odds(236));
+            assert_eq!(sc.normalize_odds(OddsInput::American(-110), BetType::Back), odds(191));
+            assert_eq!(sc.normalize_odds(OddsInput::American(150), BetType::Lay), odds(250));
+            assert_eq!(sc.normalize_odds(OddsInput::ImpliedProbability(4_000), BetType::Back), odds(250));
+        });
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(2)))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet_in_format(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                OddsInput::Fractional(3, 2),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.bet_by_id(1).get().odd, BigUint::from(odds(250)));
+
+            let ladder = sc.get_formatted_ladder(MARKET_ID, HOME);
+            assert_eq!(ladder.back_levels.len(), 1);
+            let level = ladder.back_levels.get(0);
+            assert_eq!(level.odds.american, 150);
+            assert_eq!(level.total_stake, BigUint::from(egld(2)));
+        });
+}
+
+#[test]
+fn odds_formats_reject_prices_out_of_range() {
+    let mut world = setup();
+
+    // Cota maximă încape în u64, dar profitul ei în cenți americani nu
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Odds out of range"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.get_odds_formats(u64::MAX);
+        });
+}
+
+#[test]
+fn odds_input_that_overflows_is_rejected() {
+    let mut world = setup();
+
+    for input in [
+        OddsInput::Fractional(u64::MAX / 10_000, 1),
+        OddsInput::Fractional(u64::MAX, 1),
+        OddsInput::American(i64::MAX),
+        OddsInput::American(i64::MIN),
+    ] {
+        world
+            .tx()
+            .from(BACKER)
+            .to(ROCKSTAKE)
+            .returns(ExpectError(4, "Odds out of range"))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.normalize_odds(input, BetType::Back);
+            });
+    }
+}
+
+#[test]
+fn stake_limits_per_token_and_market() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_token_stake_limits(
+                EgldOrEsdtTokenIdentifier::egld(),
+                BigUint::from(ONE_EGLD / 2),
+                BigUint::from(egld(5)),
+            );
+            sc.set_market_stake_limits(
+                MARKET_ID,
+                EgldOrEsdtTokenIdentifier::egld(),
+                BigUint::from(ONE_EGLD / 4),
+                BigUint::from(egld(3)),
+            );
+
+            let (min_stake, max_stake) =
+                sc.get_stake_limits(MARKET_ID, EgldOrEsdtTokenIdentifier::egld()).into_tuple();
+            assert_eq!(min_stake, BigUint::from(ONE_EGLD / 2));
+            assert_eq!(max_stake, BigUint::from(egld(3)));
+        });
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(4)))
+        .returns(ExpectError(4, "Stake amount out of range"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    // 1 EGLD de liability la 3.00 acoperă 0.5 EGLD de stake, exact minimul
+    place_bet(&mut world, LAYER, egld(1), 300, BetType::Lay);
+    check_bet(&mut world, 1, 0, ONE_EGLD / 2, egld(1), BetStatus::Unmatched);
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Stake amount out of range"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.update_bet(1, OptionalValue::None, OptionalValue::Some(BigUint::from(ONE_EGLD / 4)));
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Market stake limits outside token limits"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_market_stake_limits(
+                MARKET_ID,
+                EgldOrEsdtTokenIdentifier::egld(),
+                BigUint::from(egld(6)),
+                BigUint::from(egld(8)),
+            );
+        });
+
+    // Limitele tokenului mutate sub cele ale pieței lasă piața fără nicio miză validă
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_token_stake_limits(
+                EgldOrEsdtTokenIdentifier::egld(),
+                BigUint::from(ONE_EGLD / 10),
+                BigUint::from(ONE_EGLD / 5),
+            );
+        });
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(ONE_EGLD / 5))
+        .returns(ExpectError(4, "Market stake limits outside token limits"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+}
+
+#[test]
+fn markets_settle_in_a_single_whitelisted_token() {
+    let mut world = setup();
+    const STABLE_BACKER: TestAddress = TestAddress::new("stable-backer");
+    const STABLE_LAYER: TestAddress = TestAddress::new("stable-layer");
+    for bettor in [STABLE_BACKER, STABLE_LAYER] {
+        world
+            .account(bettor)
+            .nonce(1)
+            .balance(balance(egld(10)))
+            .esdt_balance(STABLE_TOKEN, balance(egld(100)));
+    }
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Token not accepted"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut selection_values = ManagedVec::new();
+            selection_values.push(1u64);
+            sc.create_market(
+                SECOND_EVENT_ID,
+                ManagedBuffer::from("FullTime Result"),
+                selection_values,
+                CLOSE_TIMESTAMP,
+                EgldOrEsdtTokenIdentifier::esdt(STABLE_TOKEN.as_str()),
+                OptionalValue::None,
+            );
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let token = EgldOrEsdtTokenIdentifier::esdt(STABLE_TOKEN.as_str());
+            sc.add_accepted_token(token.clone(), BigUint::from(egld(1)), BigUint::from(egld(10_000)));
+            let mut selection_values = ManagedVec::new();
+            selection_values.push(1u64);
+            selection_values.push(2u64);
+            let market_id = sc.create_market(
+                SECOND_EVENT_ID,
+                ManagedBuffer::from("FullTime Result"),
+                selection_values,
+                CLOSE_TIMESTAMP,
+                token,
+                OptionalValue::None,
+            );
+            assert_eq!(market_id, 2);
+        });
+
+    world
+        .tx()
+        .from(STABLE_BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(2)))
+        .returns(ExpectError(4, "Invalid payment token"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                2,
+                HOME,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    for (bettor, payment, bet_type) in [
+        (STABLE_LAYER, egld(6), BetType::Lay),
+        (STABLE_BACKER, egld(2), BetType::Back),
+    ] {
+        world
+            .tx()
+            .from(bettor)
+            .to(ROCKSTAKE)
+            .esdt(TestEsdtTransfer(STABLE_TOKEN, 0, payment as u64))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.place_bet(
+                    ManagedBuffer::from("cid"),
+                    2,
+                    HOME,
+                    BigUint::from(odds(300)),
+                    bet_type,
+                    OptionalValue::None,
+                    OptionalValue::None,
+                );
+            });
+    }
+
+    check_bet(&mut world, 2, egld(2), 0, 0, BetStatus::Matched);
+    check_bet(&mut world, 1, egld(2), egld(1), egld(6), BetStatus::PartiallyMatched);
+
+    // Expunerea în stablecoin nu se adună cu cea în EGLD
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let token = EgldOrEsdtTokenIdentifier::esdt(STABLE_TOKEN.as_str());
+            assert_eq!(sc.get_total_exposure(token), BigUint::from(egld(8)));
+            assert_eq!(sc.get_total_exposure(EgldOrEsdtTokenIdentifier::egld()), BigUint::zero());
+        });
+
+    world
+        .tx()
+        .from(STABLE_LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(1);
+            sc.withdraw(EgldOrEsdtTokenIdentifier::esdt(STABLE_TOKEN.as_str()), BigUint::from(egld(2)));
+        });
+    world
+        .check_account(STABLE_LAYER)
+        .esdt_balance(STABLE_TOKEN, balance(egld(96)))
+        .balance(balance(egld(10)));
+    world
+        .check_account(ROCKSTAKE)
+        .esdt_balance(STABLE_TOKEN, balance(egld(6)));
+}
+
+fn check_exposure(world: &mut ScenarioWorld, bettor: TestAddress, market_exposure: u128, total_exposure: u128) {
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, move |sc| {
+            let (on_market, overall) = sc.get_user_exposure(bettor.to_managed_address(), MARKET_ID).into_tuple();
+            assert_eq!(on_market, BigUint::from(market_exposure));
+            assert_eq!(overall, BigUint::from(total_exposure));
+        });
+}
+
+#[test]
+fn exposure_nets_backs_and_lays_across_selections() {
+    let mut world = setup();
+
+    // Back 1 EGLD la 3.00: pierde 1 EGLD dacă HOME nu câștigă
+    place_bet(&mut world, BACKER, egld(1), 300, BetType::Back);
+    check_exposure(&mut world, BACKER, egld(1), egld(1));
+
+    // Lay 1 EGLD la 2.00 pe aceeași selecție acoperă pierderea
+    place_bet(&mut world, BACKER, egld(1), 200, BetType::Lay);
+    check_exposure(&mut world, BACKER, 0, 0);
+
+    // Lay-ul se matchează la 2.00 și îl expune pe LAYER cu liability-ul de 1 EGLD
+    place_bet(&mut world, LAYER, egld(1), 200, BetType::Back);
+    check_exposure(&mut world, LAYER, egld(1), egld(1));
+    check_exposure(&mut world, BACKER, 0, 0);
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_max_user_exposure(EgldOrEsdtTokenIdentifier::egld(), OptionalValue::Some(BigUint::from(egld(1))));
+            assert_eq!(sc.get_total_exposure(EgldOrEsdtTokenIdentifier::egld()), BigUint::from(egld(1)));
+        });
+
+    // 2 EGLD pe AWAY lasă DRAW cu o pierdere de 2 EGLD, peste plafon
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(2)))
+        .returns(ExpectError(4, "Maximum exposure exceeded"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                2,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    // Anularea back-ului lasă doar lay-ul matched, cu 1 EGLD de liability
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(1);
+        });
+    check_exposure(&mut world, BACKER, egld(1), egld(1));
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_user_exposure_cap(
+                LAYER.to_managed_address(),
+                EgldOrEsdtTokenIdentifier::egld(),
+                OptionalValue::Some(BigUint::from(egld(5))),
+            );
+            sc.set_market_exposure_cap(MARKET_ID, OptionalValue::Some(BigUint::from(ONE_EGLD / 2)));
+        });
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(1)))
+        .returns(ExpectError(4, "Market exposure limit exceeded"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+}
+
+#[test]
+fn exposure_nets_a_dutched_book() {
+    let mut world = setup();
+
+    // Back pe toate cele trei rezultate la 3.00: oricare câștigă, profitul acoperă celelalte două mize
+    for selection_id in [HOME, 2, 3] {
+        place_bet_on(&mut world, BACKER, egld(1), selection_id, 300, BetType::Back);
+    }
+    check_exposure(&mut world, BACKER, 0, 0);
+
+    // Lay pe HOME și AWAY la 2.00: un singur câștigător, deci cel mult un liability plătit
+    place_bet_on(&mut world, LAYER, egld(1), HOME, 200, BetType::Lay);
+    place_bet_on(&mut world, LAYER, egld(1), 2, 200, BetType::Lay);
+    check_exposure(&mut world, LAYER, 0, 0);
+}
+
+#[test]
+fn exposure_covers_two_winners_in_double_chance() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let market_type_id = sc.register_market_type(MarketKind::DoubleChance, 0, ManagedBuffer::from("Double Chance"));
+            sc.create_typed_market(EVENT_ID, market_type_id, CLOSE_TIMESTAMP, EgldOrEsdtTokenIdentifier::egld(), OptionalValue::None);
+        });
+
+    // La victoria gazdelor câștigă și 1 (gazde sau egal), și 3 (gazde sau oaspeți)
+    for selection_id in [1u64, 3] {
+        world
+            .tx()
+            .from(LAYER)
+            .to(ROCKSTAKE)
+            .egld(balance(egld(1)))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.place_bet(
+                    ManagedBuffer::from("cid"),
+                    2,
+                    selection_id,
+                    BigUint::from(odds(200)),
+                    BetType::Lay,
+                    OptionalValue::None,
+                    OptionalValue::None,
+                );
+            });
+    }
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let (on_market, _) = sc.get_user_exposure(LAYER.to_managed_address(), 2).into_tuple();
+            assert_eq!(on_market, BigUint::from(egld(2)));
+        });
+}
+
+#[test]
+fn bets_from_deposited_balance() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(10)))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.deposit();
+        });
+
+    for (payment, bet_odds) in [(egld(4), 300), (egld(5), 350)] {
+        world
+            .tx()
+            .from(LAYER)
+            .to(ROCKSTAKE)
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.place_bet_from_balance(
+                    ManagedBuffer::from("cid"),
+                    MARKET_ID,
+                    HOME,
+                    BigUint::from(odds(bet_odds)),
+                    BetType::Lay,
+                    BigUint::from(payment),
+                    OptionalValue::None,
+                    OptionalValue::None,
+                );
+            });
+    }
+    check_bet(&mut world, 2, 0, egld(2), egld(5), BetStatus::Unmatched);
+    check_locked_funds(&mut world, LAYER, egld(9));
+    check_available_balance(&mut world, LAYER, egld(1));
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Insufficient balance"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet_from_balance(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                HOME,
+                BigUint::from(odds(300)),
+                BetType::Lay,
+                BigUint::from(egld(2)),
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    // Back-ul ia Lay-ul de la 3.50; anularea celuilalt readuce 4 EGLD în sold
+    place_bet(&mut world, BACKER, egld(2), 350, BetType::Back);
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.cancel_bet(1);
+            sc.withdraw(EgldOrEsdtTokenIdentifier::egld(), BigUint::from(egld(5)));
+        });
+
+    check_locked_funds(&mut world, LAYER, egld(5));
+    check_available_balance(&mut world, LAYER, 0);
+    world.check_account(LAYER).balance(balance(STARTING_BALANCE - egld(5)));
+}
+
+#[test]
+fn winnings_are_claimed_by_bettor_or_ticket_holder() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(8), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+            sc.set_market_result(EVENT_ID, 1, 2, 0);
+            sc.process_batch_bets(MARKET_ID, 10);
+
+            // Decontarea doar înregistrează plățile
+            let (payout, claimed) = sc.get_bet_payout(2).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(6)));
+            assert!(!claimed);
+        });
+    world.check_account(BACKER).balance(balance(STARTING_BALANCE - egld(4)));
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Nothing to claim"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_ids = MultiValueEncoded::new();
+            bet_ids.push(1u64);
+            sc.claim_winnings(bet_ids);
+        });
+
+    // Biletul pariului 3 ajunge la SECOND_BACKER, care încasează cu el
+    world.transfer_step(
+        TransferStep::new()
+            .from(BACKER)
+            .to(SECOND_BACKER)
+            .esdt_transfer(BET_TOKEN.eval_to_expr().as_str(), 3, "1"),
+    );
+    world
+        .tx()
+        .from(SECOND_BACKER)
+        .to(ROCKSTAKE)
+        .esdt(TestEsdtTransfer(BET_TOKEN, 3, 1))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_ids = MultiValueEncoded::new();
+            bet_ids.push(3u64);
+            sc.claim_winnings(bet_ids);
+        });
+    world
+        .check_account(SECOND_BACKER)
+        .balance(balance(STARTING_BALANCE + egld(6)));
+
+    // Biletul se întoarce la deținător, dar nu mai poate încasa a doua oară
+    world
+        .tx()
+        .from(SECOND_BACKER)
+        .to(ROCKSTAKE)
+        .esdt(TestEsdtTransfer(BET_TOKEN, 3, 1))
+        .returns(ExpectError(4, "Winnings already claimed"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_ids = MultiValueEncoded::new();
+            bet_ids.push(3u64);
+            sc.claim_winnings(bet_ids);
+        });
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.claim_all(MARKET_ID), BigUint::from(egld(6)));
+        });
+    world.check_account(BACKER).balance(balance(STARTING_BALANCE + egld(2)));
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Winnings already claimed"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut bet_ids = MultiValueEncoded::new();
+            bet_ids.push(2u64);
+            sc.claim_winnings(bet_ids);
+        });
+}
+
+#[test]
+fn first_version_result_settles_its_market() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+
+    // Prima versiune scria doar selecția câștigătoare sub cheia winningSelection
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+            sc.legacy_winning_selection(MARKET_ID).set(HOME);
+            sc.markets(MARKET_ID).update(|market| market.market_status = MarketStatus::Settled);
+
+            let (winners, _) = sc.get_market_settlement_details(MARKET_ID);
+            assert_eq!(winners.len(), 1);
+            assert_eq!(winners.get(0).selection_id, HOME);
+            assert_eq!(winners.get(0).win_fraction_bps, 10_000);
+
+            assert!(sc.process_batch_bets(MARKET_ID, 10) == ProcessingStatus::Completed);
+            assert!(sc.bet_by_id(1).get().status == BetStatus::Lost);
+            assert!(sc.bet_by_id(2).get().status == BetStatus::Win);
+        });
+}
+
+#[test]
+fn batch_settlement_resumes_from_cursor() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(8), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+    place_bet(&mut world, SECOND_BACKER, egld(2), 300, BetType::Back);
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+            assert!(sc.get_processing_progress(MARKET_ID).status == ProcessingStatus::NotStarted);
+            sc.set_market_result(EVENT_ID, 1, 2, 0);
+
+            assert!(sc.process_batch_bets(MARKET_ID, 2) == ProcessingStatus::InProgress);
+            let progress = sc.get_processing_progress(MARKET_ID);
+            assert_eq!(progress.processed_bets, 2);
+            assert_eq!(progress.total_bets, 3);
+            assert!(progress.status == ProcessingStatus::InProgress);
+            assert!(sc.bet_by_id(2).get().status == BetStatus::Win);
+            assert!(sc.bet_by_id(3).get().status == BetStatus::Matched);
+
+            // Ultimul pariu, apoi comisionul pentru cei trei pariori
+            assert!(sc.process_batch_bets(MARKET_ID, 2) == ProcessingStatus::InProgress);
+            assert!(sc.process_batch_bets(MARKET_ID, 2) == ProcessingStatus::Completed);
+            assert!(sc.markets(MARKET_ID).get().market_status == MarketStatus::Paid);
+            assert!(sc.get_processing_progress(MARKET_ID).status == ProcessingStatus::Completed);
+
+            // Un apel în plus nu mai decontează nimic
+            assert!(sc.process_batch_bets(MARKET_ID, 10) == ProcessingStatus::Completed);
+            assert_eq!(sc.get_processing_progress(MARKET_ID).processed_bets, 3);
+        });
+
+    world
+        .tx()
+        .from(SECOND_BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert_eq!(sc.claim_all(MARKET_ID), BigUint::from(egld(6)));
+        });
+}
+
+fn check_solvency(world: &mut ScenarioWorld, expected_balance: u128, expected_obligations: u128) {
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, move |sc| {
+            let (contract_balance, obligations, solvent) =
+                sc.get_solvency(EgldOrEsdtTokenIdentifier::egld()).into_tuple();
+            assert_eq!(contract_balance, BigUint::from(expected_balance));
+            assert_eq!(obligations, BigUint::from(expected_obligations));
+            assert!(solvent);
+        });
+}
+
+#[test]
+fn winning_lay_collects_liability_and_backer_stake() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(6), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+    check_solvency(&mut world, egld(8), egld(8));
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+            sc.set_market_result(EVENT_ID, 1, 0, 1);
+            sc.process_batch_bets(MARKET_ID, 10);
+
+            let (payout, _) = sc.get_bet_payout(1).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(6)));
+            assert!(sc.bet_by_id(2).get().status == BetStatus::Lost);
+        });
+
+    // Partea nematched de 2 EGLD a revenit în sold la închidere, restul e de încasat
+    check_locked_funds(&mut world, LAYER, 0);
+    check_locked_funds(&mut world, BACKER, 0);
+    check_available_balance(&mut world, LAYER, egld(2));
+    check_solvency(&mut world, egld(8), egld(8));
+
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.claim_all(MARKET_ID);
+        });
+    world.check_account(LAYER).balance(balance(STARTING_BALANCE));
+    check_solvency(&mut world, egld(2), egld(2));
+}
+
+#[test]
+fn commission_is_charged_on_net_market_winnings() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_commission_rate(500);
+            sc.set_market_commission_rate(MARKET_ID, OptionalValue::Some(1_000));
+            sc.set_discount_tier(1, 5_000);
+            sc.set_user_discount_tier(SECOND_BACKER.to_managed_address(), 1);
+            assert_eq!(sc.get_commission_rate(BACKER.to_managed_address(), MARKET_ID), 1_000);
+            assert_eq!(sc.get_commission_rate(SECOND_BACKER.to_managed_address(), MARKET_ID), 500);
+        });
+
+    // BACKER câștigă 4 EGLD pe HOME, dar pierde 1 EGLD pe AWAY: net 3 EGLD
+    place_bet(&mut world, LAYER, egld(12), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+    place_bet(&mut world, SECOND_BACKER, egld(4), 300, BetType::Back);
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(1)))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                2,
+                BigUint::from(odds(300)),
+                BetType::Back,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+    world
+        .tx()
+        .from(LAYER)
+        .to(ROCKSTAKE)
+        .egld(balance(egld(2)))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                2,
+                BigUint::from(odds(300)),
+                BetType::Lay,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+            sc.set_market_result(EVENT_ID, 1, 1, 0);
+            sc.process_batch_bets(MARKET_ID, 20);
+
+            // 10% din 3 EGLD pentru BACKER, 5% din 8 EGLD pentru SECOND_BACKER
+            let (payout, _) = sc.get_bet_payout(2).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(6) - egld(3) / 10));
+            let (payout, _) = sc.get_bet_payout(3).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(12) - egld(8) / 20));
+            assert_eq!(
+                sc.get_fee_treasury(EgldOrEsdtTokenIdentifier::egld()),
+                BigUint::from(egld(3) / 10 + egld(8) / 20)
+            );
+        });
+    check_solvency(&mut world, egld(21), egld(21));
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.withdraw_fees(EgldOrEsdtTokenIdentifier::egld(), OptionalValue::None);
+        });
+    world.check_account(OWNER).balance(balance(egld(3) / 10 + egld(8) / 20));
+    check_solvency(&mut world, egld(21) - egld(3) / 10 - egld(8) / 20, egld(21) - egld(3) / 10 - egld(8) / 20);
+}
+
+#[test]
+fn voided_market_returns_every_stake() {
+    let mut world = setup();
+    const ORACLE: TestAddress = TestAddress::new("oracle");
+    world.account(ORACLE).nonce(1);
+
+    place_bet(&mut world, LAYER, egld(6), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+    place_bet(&mut world, SECOND_BACKER, egld(1), 250, BetType::Back);
+
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Only owner or oracle"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.void_market(MARKET_ID, ManagedBuffer::from("abandoned"));
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_oracle(ORACLE.to_managed_address());
+        });
+    world
+        .tx()
+        .from(ORACLE)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.void_market(MARKET_ID, ManagedBuffer::from("abandoned"));
+            assert!(sc.markets(MARKET_ID).get().market_status == MarketStatus::Voided);
+
+            assert!(sc.process_batch_bets(MARKET_ID, 1) == ProcessingStatus::InProgress);
+            assert!(sc.process_batch_bets(MARKET_ID, 5) == ProcessingStatus::Completed);
+            assert!(sc.get_processing_progress(MARKET_ID).status == ProcessingStatus::Completed);
+            assert!(sc.process_batch_bets(MARKET_ID, 5) == ProcessingStatus::Completed);
+        });
+
+    for bet_id in 1..=3 {
+        world
+            .query()
+            .to(ROCKSTAKE)
+            .whitebox(rockstake::contract_obj, move |sc| {
+                assert!(sc.bet_by_id(bet_id).get().status == BetStatus::Canceled);
+            });
+    }
+    for (bettor, refund) in [(LAYER, egld(6)), (BACKER, egld(2)), (SECOND_BACKER, egld(1))] {
+        check_locked_funds(&mut world, bettor, 0);
+        check_available_balance(&mut world, bettor, refund);
+        check_exposure(&mut world, bettor, 0, 0);
+    }
+    check_solvency(&mut world, egld(9), egld(9));
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Market already finished"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.void_market(MARKET_ID, ManagedBuffer::from("again"));
+        });
+}
+
+#[test]
+fn dead_heat_splits_the_pot_by_fraction() {
+    let mut world = setup();
+
+    place_bet(&mut world, LAYER, egld(4), 300, BetType::Lay);
+    place_bet(&mut world, BACKER, egld(2), 300, BetType::Back);
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Invalid outcome fraction"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut results = MultiValueEncoded::new();
+            results.push((HOME, 0u64, 0u64).into());
+            sc.set_market_results(MARKET_ID, results);
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let mut results = MultiValueEncoded::new();
+            results.push((HOME, 5_000u64, 0u64).into());
+            results.push((2u64, 5_000u64, 0u64).into());
+            sc.set_market_results(MARKET_ID, results);
+            sc.process_batch_bets(MARKET_ID, 10);
+
+            let (winners, status) = sc.get_market_settlement_details(MARKET_ID);
+            assert_eq!(winners.len(), 2);
+            assert_eq!(winners.get(1).selection_id, 2);
+            assert_eq!(winners.get(1).win_fraction_bps, 5_000);
+            assert!(status == MarketStatus::Paid);
+
+            // Potul de 6 EGLD se împarte pe jumătate
+            let (payout, _) = sc.get_bet_payout(1).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(3)));
+            let (payout, _) = sc.get_bet_payout(2).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(3)));
+        });
+    check_solvency(&mut world, egld(6), egld(6));
+}
+
+fn place_bet_on(
+    world: &mut ScenarioWorld,
+    bettor: TestAddress,
+    payment: u128,
+    selection_id: u64,
+    odds_hundredths: u64,
+    bet_type: BetType,
+) {
+    world
+        .tx()
+        .from(bettor)
+        .to(ROCKSTAKE)
+        .egld(balance(payment))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.place_bet(
+                ManagedBuffer::from("cid"),
+                MARKET_ID,
+                selection_id,
+                BigUint::from(odds(odds_hundredths)),
+                bet_type,
+                OptionalValue::None,
+                OptionalValue::None,
+            );
+        });
+}
+
+#[test]
+fn half_win_push_and_half_loss_outcomes() {
+    let mut world = setup();
+
+    place_bet_on(&mut world, LAYER, egld(4), HOME, 300, BetType::Lay);
+    place_bet_on(&mut world, BACKER, egld(2), HOME, 300, BetType::Back);
+    place_bet_on(&mut world, LAYER, egld(2), 2, 200, BetType::Lay);
+    place_bet_on(&mut world, SECOND_BACKER, egld(2), 2, 200, BetType::Back);
+    place_bet_on(&mut world, SECOND_LAYER, egld(2), 3, 200, BetType::Lay);
+    place_bet_on(&mut world, BACKER, egld(2), 3, 200, BetType::Back);
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(MARKET_ID);
+            let mut results = MultiValueEncoded::new();
+            results.push((HOME, 5_000u64, 5_000u64).into());
+            results.push((2u64, 0u64, 10_000u64).into());
+            results.push((3u64, 0u64, 5_000u64).into());
+            sc.set_market_results(MARKET_ID, results);
+            sc.process_batch_bets(MARKET_ID, 20);
+
+            let counters = sc.get_settlement_counters(MARKET_ID);
+            assert_eq!((counters.won, counters.lost, counters.voided), (2, 2, 2));
+        });
+
+    // Pe HOME backer-ul ia jumătate din pot plus jumătate din miză
+    for (bet_id, status, payout) in [
+        (1, BetStatus::HalfLost, egld(2)),
+        (2, BetStatus::HalfWon, egld(4)),
+        (3, BetStatus::Push, egld(2)),
+        (4, BetStatus::Push, egld(2)),
+        (5, BetStatus::HalfWon, egld(3)),
+        (6, BetStatus::HalfLost, egld(1)),
+    ] {
+        world
+            .query()
+            .to(ROCKSTAKE)
+            .whitebox(rockstake::contract_obj, move |sc| {
+                assert!(sc.bet_by_id(bet_id).get().status == status);
+                let (recorded, _) = sc.get_bet_payout(bet_id).into_tuple();
+                assert_eq!(recorded, BigUint::from(payout));
+            });
+    }
+    check_solvency(&mut world, egld(14), egld(14));
+}
+
+/// Câștigătorii pe care resolver-ul tipului îi dă pentru un scor, ca (selecție, win, void)
+fn resolve(
+    sc: &rockstake::ContractObj<DebugApi>,
+    kind: MarketKind,
+    line: i64,
+    score: (u32, u32),
+    half_time: Option<(u32, u32)>,
+) -> Vec<(u64, u64, u64)> {
+    let market_type = MarketType { kind, line, description: ManagedBuffer::new() };
+    sc.resolve_market_type(&market_type, score.0, score.1, half_time)
+        .iter()
+        .map(|winner| (winner.selection_id, winner.win_fraction_bps, winner.void_fraction_bps))
+        .collect()
+}
+
+#[test]
+fn catalogue_resolvers_map_scores_to_selections() {
+    let mut world = setup();
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            // Tipurile fixe de dinainte rămân 1-3
+            assert!(sc.get_market_type(2).kind == MarketKind::OverUnder);
+            assert_eq!(sc.get_market_type(2).line, 250);
+
+            assert_eq!(resolve(&sc, MarketKind::FullTimeResult, 0, (0, 2), None), vec![(2, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::OverUnder, 250, (2, 1), None), vec![(1, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::OverUnder, 300, (2, 1), None), vec![(1, 0, 10_000), (2, 0, 10_000)]);
+            // O/U 2.25 cu două goluri: Under câștigă jumătate, Over pierde jumătate
+            assert_eq!(resolve(&sc, MarketKind::OverUnder, 225, (1, 1), None), vec![(1, 0, 5_000), (2, 5_000, 5_000)]);
+            assert_eq!(resolve(&sc, MarketKind::AsianHandicap, -150, (2, 1), None), vec![(2, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::AsianHandicap, -75, (2, 1), None), vec![(1, 5_000, 5_000), (2, 0, 5_000)]);
+            assert_eq!(resolve(&sc, MarketKind::EuropeanHandicap, -100, (2, 1), None), vec![(3, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::DoubleChance, 0, (1, 1), None), vec![(1, 10_000, 0), (2, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::DrawNoBet, 0, (1, 1), None), vec![(1, 0, 10_000), (2, 0, 10_000)]);
+            assert_eq!(resolve(&sc, MarketKind::BothTeamsToScore, 0, (1, 0), None), vec![(2, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::CorrectScore, 3, (2, 1), None), vec![(10, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::CorrectScore, 3, (4, 0), None), vec![(17, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::HalfTimeFullTime, 0, (2, 1), Some((0, 0))), vec![(7, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::WinningMargin, 3, (5, 1), None), vec![(3, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::WinningMargin, 3, (0, 1), None), vec![(4, 10_000, 0)]);
+            assert_eq!(resolve(&sc, MarketKind::WinningMargin, 3, (2, 2), None), vec![(7, 10_000, 0)]);
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Invalid market line"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.register_market_type(MarketKind::EuropeanHandicap, -150, ManagedBuffer::from("Handicap -1.5"));
+        });
+}
+
+#[test]
+fn typed_market_settles_from_reported_score() {
+    let mut world = setup();
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let market_type_id = sc.register_market_type(
+                MarketKind::AsianHandicap,
+                -75,
+                ManagedBuffer::from("Asian Handicap -0.75"),
+            );
+            assert_eq!(market_type_id, 4);
+            let market_id = sc.create_typed_market(
+                EVENT_ID,
+                market_type_id,
+                CLOSE_TIMESTAMP,
+                EgldOrEsdtTokenIdentifier::egld(),
+                OptionalValue::None,
+            );
+            assert_eq!(market_id, 2);
+            assert_eq!(sc.get_market_type_of(market_id), market_type_id);
+            assert_eq!(sc.markets(market_id).get().selections.len(), 2);
+
+            let mut selection_values = ManagedVec::new();
+            selection_values.push(1u64);
+            selection_values.push(2u64);
+            sc.create_market(
+                SECOND_EVENT_ID,
+                ManagedBuffer::from("Special"),
+                selection_values,
+                CLOSE_TIMESTAMP,
+                EgldOrEsdtTokenIdentifier::egld(),
+                OptionalValue::None,
+            );
+        });
+
+    for (bettor, bet_type) in [(LAYER, BetType::Lay), (BACKER, BetType::Back)] {
+        world
+            .tx()
+            .from(bettor)
+            .to(ROCKSTAKE)
+            .egld(balance(egld(2)))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.place_bet(
+                    ManagedBuffer::from("cid"),
+                    2,
+                    HOME,
+                    BigUint::from(odds(200)),
+                    bet_type,
+                    OptionalValue::None,
+                    OptionalValue::None,
+                );
+            });
+    }
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(2);
+            sc.set_market_score(2, 2, 1, OptionalValue::None);
+            sc.process_batch_bets(2, 10);
+        });
+
+    // Gazdele câștigă la un gol: la -0.75 backer-ul ia jumătate din pot plus jumătate din miză
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            assert!(sc.bet_by_id(2).get().status == BetStatus::HalfWon);
+            let (payout, _) = sc.get_bet_payout(2).into_tuple();
+            assert_eq!(payout, BigUint::from(egld(3)));
+            assert!(sc.bet_by_id(1).get().status == BetStatus::HalfLost);
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Market has no type"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(3);
+            sc.set_market_score(3, 2, 1, OptionalValue::None);
+        });
+}
+
+#[test]
+fn market_result_settles_the_event_market_of_its_type() {
+    let mut world = setup();
+
+    // Piețele tipizate sunt create în altă ordine decât în catalog
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            for market_type_id in [3u64, 2] {
+                sc.create_typed_market(
+                    EVENT_ID,
+                    market_type_id,
+                    CLOSE_TIMESTAMP,
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    OptionalValue::None,
+                );
+            }
+        });
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_market_close(2);
+            sc.process_market_close(3);
+            sc.set_market_result(EVENT_ID, 2, 2, 1);
+        });
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let (winners, status) = sc.get_market_settlement_details(3);
+            assert!(status == MarketStatus::Settled);
+            assert_eq!(winners.len(), 1);
+            assert_eq!(winners.get(0).selection_id, 1);
+
+            let (winners, status) = sc.get_market_settlement_details(2);
+            assert!(status == MarketStatus::Closed);
+            assert!(winners.is_empty());
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_market_result(EVENT_ID, 3, 1, 0);
+            let (winners, _) = sc.get_market_settlement_details(2);
+            assert_eq!(winners.get(0).selection_id, 2);
+        });
+
+    for (market_type_id, message) in [(0u64, "Invalid market type"), (4, "No market of this type on the event")] {
+        world
+            .tx()
+            .from(OWNER)
+            .to(ROCKSTAKE)
+            .returns(ExpectError(4, message))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.set_market_result(EVENT_ID, market_type_id, 2, 1);
+            });
+    }
+}
+
+#[test]
+fn event_markets_close_at_kickoff() {
+    let mut world = setup();
+
+    world
+        .query()
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            let event = sc.get_event(EVENT_ID);
+            assert_eq!(event.home, ManagedBuffer::from("Home"));
+            assert!(event.status == EventStatus::Scheduled);
+            assert_eq!(sc.get_events_by_timestamp(CLOSE_TIMESTAMP).len(), 2);
+            assert_eq!(sc.get_events_by_competition(ManagedBuffer::from("Liga 1")).len(), 2);
+        });
+
+    for (event_id, close_timestamp, error) in [
+        (3, CLOSE_TIMESTAMP, "Invalid event"),
+        (EVENT_ID, CLOSE_TIMESTAMP + 1, "Market must close by event start"),
+    ] {
+        world
+            .tx()
+            .from(OWNER)
+            .to(ROCKSTAKE)
+            .returns(ExpectError(4, error))
+            .whitebox(rockstake::contract_obj, |sc| {
+                sc.create_typed_market(event_id, 1, close_timestamp, EgldOrEsdtTokenIdentifier::egld(), OptionalValue::None);
+            });
+    }
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Event start not reached"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_event_markets(CLOSE_TIMESTAMP);
+        });
+
+    world.current_block().block_timestamp(CLOSE_TIMESTAMP);
+    world
+        .tx()
+        .from(BACKER)
+        .to(ROCKSTAKE)
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.process_event_markets(CLOSE_TIMESTAMP);
+            assert!(sc.get_market_status(MARKET_ID) == MarketStatus::Closed);
+            assert!(sc.get_event(EVENT_ID).status == EventStatus::Live);
+        });
+
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Event not scheduled"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_event_status(SECOND_EVENT_ID, EventStatus::Cancelled);
+            sc.create_typed_market(SECOND_EVENT_ID, 1, CLOSE_TIMESTAMP + 10, EgldOrEsdtTokenIdentifier::egld(), OptionalValue::None);
+        });
+
+    // Statusul unui eveniment doar avansează
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Invalid event status transition"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_event_status(EVENT_ID, EventStatus::Scheduled);
+        });
+    world
+        .tx()
+        .from(OWNER)
+        .to(ROCKSTAKE)
+        .returns(ExpectError(4, "Event already finished"))
+        .whitebox(rockstake::contract_obj, |sc| {
+            sc.set_event_status(SECOND_EVENT_ID, EventStatus::Cancelled);
+            sc.set_event_status(SECOND_EVENT_ID, EventStatus::Live);
+        });
+}